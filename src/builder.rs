@@ -0,0 +1,362 @@
+use crate::{
+    path_key, AsyncMarkers, Error, FeatureMatch, Harness, HarnessConfig, MetadataMarkers, Test,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use walkdir::WalkDir;
+
+/// Configures and constructs
+/// a `Harness`
+#[derive(Debug, Clone, Default)]
+pub struct HarnessBuilder {
+    roots: Vec<PathBuf>,
+    sorted: bool,
+    max_depth: Option<usize>,
+    follow_links: bool,
+    canonicalize: bool,
+    collect_skipped: bool,
+    transform: Option<Transform>,
+    harness_dir: Option<PathBuf>,
+    include_overrides: HashMap<String, PathBuf>,
+    validate_includes: bool,
+    deny_duplicate_keys: bool,
+    global_includes: Vec<String>,
+    async_markers: AsyncMarkers,
+    metadata_markers: MetadataMarkers,
+    config: HarnessConfig,
+}
+
+/// The checks from a `HarnessConfig`
+/// that need a parsed test
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Filter {
+    skip_ids: HashSet<String>,
+    features: Vec<String>,
+    exclude_features: Vec<String>,
+    feature_match: FeatureMatch,
+}
+
+impl Filter {
+    pub(crate) fn accepts(&self, test: &Test) -> bool {
+        self.accepts_metadata(test.desc.ids().map(|(_, id)| id), &test.desc.features)
+    }
+
+    /// If a test with these ids and features
+    /// passes the filter
+    pub(crate) fn accepts_metadata<'a>(
+        &self,
+        mut ids: impl Iterator<Item = &'a str>,
+        features: &[String],
+    ) -> bool {
+        let skipped = ids.any(|id| self.skip_ids.contains(id));
+        !skipped
+            && self.features.iter().all(|f| self.has_feature(features, f))
+            && !self
+                .exclude_features
+                .iter()
+                .any(|f| self.has_feature(features, f))
+    }
+
+    fn has_feature(&self, features: &[String], feature: &str) -> bool {
+        features
+            .iter()
+            .any(|declared| self.feature_match.matches(declared, feature))
+    }
+
+    /// The fewest tests that can be yielded
+    /// from `remaining` paths
+    pub(crate) fn lower_bound(&self, remaining: usize) -> usize {
+        let unfiltered = self.skip_ids.is_empty()
+            && self.features.is_empty()
+            && self.exclude_features.is_empty();
+        if unfiltered {
+            remaining
+        } else {
+            0
+        }
+    }
+}
+
+/// A shared function applied to
+/// the body of every test
+#[derive(Clone)]
+pub(crate) struct Transform(Arc<dyn Fn(&str) -> String + Send + Sync>);
+
+impl Transform {
+    pub(crate) fn apply(&self, body: &str) -> String {
+        (self.0)(body)
+    }
+}
+
+impl std::fmt::Debug for Transform {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("Transform(..)")
+    }
+}
+
+impl PartialEq for Transform {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl HarnessBuilder {
+    /// Provide the root path for the
+    /// test directory from the test262 repository
+    pub fn new<P: AsRef<Path>>(test_root: P) -> Self {
+        Self::default().root(test_root)
+    }
+
+    /// Add another root path, the tests found
+    /// in each are chained together in the
+    /// order the roots were added
+    pub fn root<P: AsRef<Path>>(mut self, test_root: P) -> Self {
+        self.roots.push(test_root.as_ref().to_path_buf());
+        self
+    }
+
+    /// Sort the collected test paths lexically
+    /// so iteration order doesn't depend on the
+    /// file system, defaults to `false`
+    pub fn sorted(mut self, yes: bool) -> Self {
+        self.sorted = yes;
+        self
+    }
+
+    /// Don't descend more than `depth`
+    /// directories below each root
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Follow symbolic links while walking
+    /// each root, defaults to `false`
+    ///
+    /// A link pointing at one of its own ancestors
+    /// creates a cycle, which surfaces as an
+    /// `Error::WalkDir` from `build`
+    pub fn follow_links(mut self, yes: bool) -> Self {
+        self.follow_links = yes;
+        self
+    }
+
+    /// Make every root and test path absolute
+    /// with symbolic links resolved, so
+    /// `Test::path` doesn't depend on the working
+    /// directory, defaults to `false`
+    ///
+    /// This costs a system call for each path
+    pub fn canonicalize(mut self, yes: bool) -> Self {
+        self.canonicalize = yes;
+        self
+    }
+
+    /// Record the files ignored for their
+    /// extension while walking, see
+    /// `Harness::skipped`, defaults to `false`
+    pub fn collect_skipped(mut self, yes: bool) -> Self {
+        self.collect_skipped = yes;
+        self
+    }
+
+    /// Rewrite the body of every test, e.g. to
+    /// inject instrumentation or a prelude
+    ///
+    /// The result is what `Test::body` returns,
+    /// `Test::source` and the license and metadata
+    /// found in it are left untouched
+    pub fn transform(mut self, f: impl Fn(&str) -> String + Send + Sync + 'static) -> Self {
+        self.transform = Some(Transform(Arc::new(f)));
+        self
+    }
+
+    /// The test262 `harness` directory that
+    /// includes are resolved against, defaults
+    /// to `harness` next to the first root
+    pub fn harness_dir<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.harness_dir = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Load the include `name`, e.g.
+    /// `doneprintHandle.js`, from `path` rather
+    /// than the harness directory, where an engine
+    /// ships its own version of a harness file
+    ///
+    /// This applies everywhere includes are
+    /// resolved, such as `validate_includes` and
+    /// `Harness::with_resolved_includes`
+    pub fn override_include<S: Into<String>, P: AsRef<Path>>(mut self, name: S, path: P) -> Self {
+        self.include_overrides
+            .insert(name.into(), path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Check that every effective include of a
+    /// test exists in the harness directory while
+    /// iterating, a missing one is reported as an
+    /// `Error::MissingInclude`, defaults to `false`
+    pub fn validate_includes(mut self, yes: bool) -> Self {
+        self.validate_includes = yes;
+        self
+    }
+
+    /// Report a key repeated at the top level of
+    /// a metadata block, which serde_yaml would
+    /// otherwise resolve to one of the values, as
+    /// an `Error::DuplicateMetadataKey`, defaults
+    /// to `false`
+    pub fn deny_duplicate_keys(mut self, yes: bool) -> Self {
+        self.deny_duplicate_keys = yes;
+        self
+    }
+
+    /// Harness files, e.g. a polyfill, to load
+    /// ahead of every test, see
+    /// `Test::effective_includes`
+    pub fn global_includes(mut self, includes: Vec<String>) -> Self {
+        self.global_includes = includes;
+        self
+    }
+
+    /// The lines a fork's doneprintHandle.js
+    /// prints when an `async` test completes
+    /// and before the error when one fails, see
+    /// `Test::async_pass_marker`
+    pub fn async_markers(mut self, pass: String, fail_prefix: String) -> Self {
+        self.async_markers = AsyncMarkers { pass, fail_prefix };
+        self
+    }
+
+    /// The markers opening and closing the
+    /// metadata block of every test, e.g. `/*===`
+    /// and `===*/` for a suite that doesn't use
+    /// the standard `/*---` and `---*/`
+    pub fn metadata_delimiters(mut self, open: String, close: String) -> Self {
+        self.metadata_markers = MetadataMarkers { open, close };
+        self
+    }
+
+    /// Walk the `staging` directory directly
+    /// below each root, which holds tests that
+    /// aren't stable yet, defaults to `true`
+    pub fn include_staging(mut self, yes: bool) -> Self {
+        self.config.include_staging = yes;
+        self
+    }
+
+    /// Replace the options covered
+    /// by `HarnessConfig`, including
+    /// `include_staging`
+    pub fn config(mut self, config: HarnessConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Walk each root and collect the
+    /// test paths
+    pub fn build(mut self) -> Result<Harness, Error> {
+        if self.canonicalize {
+            for root in &mut self.roots {
+                *root = root.canonicalize()?;
+            }
+        }
+        let mut test_paths = Vec::new();
+        let mut skipped = Vec::new();
+        for root in &self.roots {
+            test_paths.extend(self.collect_paths(root, &mut skipped)?);
+        }
+        if self.canonicalize {
+            for path in &mut test_paths {
+                *path = path.canonicalize()?;
+            }
+        }
+        if self.sorted {
+            test_paths.sort();
+        }
+        let roots = &self.roots;
+        let harness_dir = self.harness_dir.clone().or_else(|| {
+            let root = roots.first()?;
+            Some(root.parent().unwrap_or(root).join("harness"))
+        });
+        Ok(Harness {
+            roots: self.roots,
+            test_paths,
+            skipped,
+            idx: 0,
+            transform: self.transform,
+            harness_dir,
+            include_overrides: Arc::new(self.include_overrides),
+            validate_includes: self.validate_includes,
+            deny_duplicate_keys: self.deny_duplicate_keys,
+            global_includes: self.global_includes.into(),
+            async_markers: Arc::new(self.async_markers),
+            metadata_markers: Arc::new(self.metadata_markers),
+            filter: Filter {
+                skip_ids: self.config.skip,
+                features: self.config.features,
+                exclude_features: self.config.exclude_features,
+                feature_match: self.config.feature_match,
+            },
+            cache: None,
+        })
+    }
+
+    fn collect_paths(
+        &self,
+        test_root: &Path,
+        skipped: &mut Vec<PathBuf>,
+    ) -> Result<Vec<PathBuf>, Error> {
+        let mut walker = WalkDir::new(test_root)
+            .min_depth(1)
+            .follow_links(self.follow_links);
+        if let Some(depth) = self.max_depth {
+            walker = walker.max_depth(depth);
+        }
+        let config = &self.config;
+        let test_paths = walker
+            .into_iter()
+            .filter_entry(|entry| {
+                if entry.depth() != 1 || !entry.file_type().is_dir() {
+                    return true;
+                }
+                match entry.file_name().to_str() {
+                    Some("staging") => config.include_staging,
+                    Some("annexB") => config.include_annex_b,
+                    _ => true,
+                }
+            })
+            .filter_map(|e| {
+                let entry = match e {
+                    Err(e) => return Some(Err(e)),
+                    Ok(e) => e,
+                };
+                let path = entry.path();
+                if path.is_dir() {
+                    return None;
+                }
+                let ext = path.extension().and_then(|ext| ext.to_str());
+                if !ext.is_some_and(|ext| config.extensions.iter().any(|e| e == ext)) {
+                    if self.collect_skipped {
+                        skipped.push(path.to_path_buf());
+                    }
+                    return None;
+                }
+                let stem = path.file_stem()?.to_str()?;
+                if stem.ends_with("_FIXTURE") && !config.include_fixtures {
+                    return None;
+                }
+                let relative = path.strip_prefix(test_root).unwrap_or(path);
+                if config.skip.contains(&path_key(relative)) {
+                    return None;
+                }
+                Some(Ok(path.to_path_buf()))
+            })
+            .collect::<Result<Vec<PathBuf>, walkdir::Error>>()?;
+        Ok(test_paths)
+    }
+}
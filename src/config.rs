@@ -0,0 +1,56 @@
+use crate::FeatureMatch;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// The options of a `Harness` in one
+/// place, so they can be loaded from a
+/// file checked in alongside a runner
+///
+/// Every field is optional when deserializing,
+/// see `Harness::with_config`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct HarnessConfig {
+    /// The extensions of test files,
+    /// without the leading `.`, defaults
+    /// to `["js"]`
+    pub extensions: Vec<String>,
+    /// Yield the `_FIXTURE` files imported by
+    /// module tests, these usually have no
+    /// metadata, defaults to `false`
+    pub include_fixtures: bool,
+    /// Walk the `staging` directory,
+    /// defaults to `true`
+    pub include_staging: bool,
+    /// Walk the `annexB` directory,
+    /// defaults to `true`
+    pub include_annex_b: bool,
+    /// Paths relative to the test root, using `/`
+    /// as a separator, or ids of tests to skip
+    pub skip: HashSet<String>,
+    /// Only yield tests that list
+    /// every one of these features
+    pub features: Vec<String>,
+    /// Don't yield tests that list
+    /// any of these features
+    pub exclude_features: Vec<String>,
+    /// How `features` and `exclude_features` are
+    /// compared with those of each test, defaults
+    /// to `exact`
+    pub feature_match: FeatureMatch,
+}
+
+impl Default for HarnessConfig {
+    fn default() -> Self {
+        Self {
+            extensions: vec!["js".to_string()],
+            include_fixtures: false,
+            include_staging: true,
+            include_annex_b: true,
+            skip: HashSet::new(),
+            features: Vec::new(),
+            exclude_features: Vec::new(),
+            feature_match: FeatureMatch::Exact,
+        }
+    }
+}
@@ -5,6 +5,58 @@ pub enum Error {
     WalkDir(walkdir::Error),
     Yaml(serde_yaml::Error),
     DescriptionInvalid(PathBuf),
+    UnknownFlag(String),
+    /// The path is a directory or doesn't
+    /// have a `.js` or `.mjs` extension
+    NotATestFile(PathBuf),
+    /// A negative test expects an
+    /// error that isn't one test262 uses
+    UnknownErrorKind {
+        path: PathBuf,
+        kind: String,
+    },
+    /// A test depends on a harness file
+    /// that doesn't exist
+    MissingInclude {
+        test: PathBuf,
+        include: String,
+    },
+    /// The metadata block has nothing but
+    /// whitespace and comments in it
+    EmptyMetadata {
+        path: PathBuf,
+    },
+    /// A metadata block repeats a top level
+    /// key, see `HarnessBuilder::deny_duplicate_keys`
+    DuplicateMetadataKey {
+        path: PathBuf,
+        key: String,
+    },
+    /// `Test::inline_harness` was asked for a
+    /// module test, which can't be a script
+    InlineModule(PathBuf),
+    /// `Harness::skip_to` was given an
+    /// index past the last test
+    IndexOutOfBounds {
+        index: usize,
+        len: usize,
+    },
+    /// The metadata block could not be
+    /// deserialized, `line` and `column`
+    /// are relative to the whole file
+    Metadata {
+        path: PathBuf,
+        line: usize,
+        column: usize,
+        /// The field serde reported the
+        /// error for, e.g. `negative.phase`
+        field: Option<String>,
+        /// The offending line
+        snippet: String,
+        /// The whole metadata block
+        metadata: String,
+        error: serde_yaml::Error,
+    },
 }
 
 impl std::fmt::Display for Error {
@@ -16,11 +68,162 @@ impl std::fmt::Display for Error {
             Error::DescriptionInvalid(path) => {
                 write!(f, "Unable to extract description for {:?}", path)
             }
+            Error::UnknownFlag(flag) => write!(f, "Unknown flag `{}`", flag),
+            Error::NotATestFile(path) => {
+                write!(f, "{} is not a .js or .mjs test file", path.display())
+            }
+            Error::UnknownErrorKind { path, kind } => write!(
+                f,
+                "Unknown negative error type `{}` in {}",
+                kind,
+                path.display()
+            ),
+            Error::MissingInclude { test, include } => write!(
+                f,
+                "{} includes `{}` which is not in the harness directory",
+                test.display(),
+                include
+            ),
+            Error::EmptyMetadata { path } => {
+                write!(f, "The metadata block of {} is empty", path.display())
+            }
+            Error::DuplicateMetadataKey { path, key } => write!(
+                f,
+                "The metadata of {} has more than one `{}` key",
+                path.display(),
+                key
+            ),
+            Error::InlineModule(path) => write!(
+                f,
+                "{} is a module so its harness can't be inlined",
+                path.display()
+            ),
+            Error::IndexOutOfBounds { index, len } => write!(
+                f,
+                "Index {} is out of bounds for a harness of {} tests",
+                index, len
+            ),
+            Error::Metadata {
+                path,
+                line,
+                column,
+                field,
+                snippet,
+                metadata,
+                error,
+            } => {
+                write!(
+                    f,
+                    "Invalid metadata in {}:{}:{}",
+                    path.display(),
+                    line,
+                    column
+                )?;
+                if let Some(field) = field {
+                    write!(f, " for `{}`", field)?;
+                }
+                writeln!(f, ": {}", yaml_message(error))?;
+                writeln!(f, "  --> {}", snippet.trim())?;
+                write!(f, "  metadata:")?;
+                for line in metadata.lines() {
+                    write!(f, "\n    {}", line)?;
+                }
+                Ok(())
+            }
         }
     }
 }
 impl std::error::Error for Error {}
 
+/// A mismatch between a test's
+/// metadata and its body
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationError {
+    /// The test is flagged `async` but never
+    /// calls `$DONE`, directly or through
+    /// `asyncTest` from asyncHelpers.js
+    AsyncWithoutDone,
+    /// The test calls `$DONE` without the
+    /// `async` flag, so doneprintHandle.js
+    /// won't be included
+    DoneWithoutAsync,
+    /// The test is flagged `raw`, which always
+    /// runs non-strict as is, alongside a flag
+    /// picking a strictness
+    RawConflict(crate::Flag),
+}
+
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ValidationError::AsyncWithoutDone => {
+                write!(f, "async test never calls $DONE")
+            }
+            ValidationError::DoneWithoutAsync => {
+                write!(f, "test calls $DONE without the async flag")
+            }
+            ValidationError::RawConflict(flag) => {
+                write!(f, "raw test is also flagged `{}`", flag)
+            }
+        }
+    }
+}
+impl std::error::Error for ValidationError {}
+
+/// A problem `Harness::lenient` worked
+/// around rather than failing the test for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Warning {
+    /// No license header was found
+    /// ahead of the metadata
+    MissingLicense,
+    /// The metadata block has nothing but
+    /// whitespace and comments in it
+    EmptyMetadata,
+    /// The metadata block isn't a YAML map,
+    /// every field was left empty
+    InvalidYaml(String),
+    /// A field couldn't be deserialized
+    /// so was left empty
+    InvalidField { field: String, message: String },
+    /// An entry of `flags` that isn't a
+    /// known flag was dropped
+    UnknownFlag(String),
+    /// An entry of `locale` doesn't look
+    /// like a BCP 47 language tag
+    MalformedLocale(String),
+    /// A negative test expects an error
+    /// that isn't one test262 uses
+    UnknownErrorKind(String),
+    /// An effective include isn't in the
+    /// harness directory, see
+    /// `HarnessBuilder::validate_includes`
+    MissingInclude(String),
+}
+
+impl std::fmt::Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Warning::MissingLicense => write!(f, "no license header"),
+            Warning::EmptyMetadata => write!(f, "the metadata block is empty"),
+            Warning::InvalidYaml(message) => {
+                write!(f, "the metadata could not be read: {}", message)
+            }
+            Warning::InvalidField { field, message } => {
+                write!(f, "`{}` was ignored: {}", field, message)
+            }
+            Warning::UnknownFlag(flag) => write!(f, "unknown flag `{}` was ignored", flag),
+            Warning::MalformedLocale(locale) => write!(f, "malformed locale `{}`", locale),
+            Warning::UnknownErrorKind(kind) => {
+                write!(f, "unknown negative error type `{}`", kind)
+            }
+            Warning::MissingInclude(include) => {
+                write!(f, "`{}` is not in the harness directory", include)
+            }
+        }
+    }
+}
+
 impl From<std::io::Error> for Error {
     fn from(other: std::io::Error) -> Self {
         Self::Io(other)
@@ -38,3 +241,49 @@ impl From<serde_yaml::Error> for Error {
         Self::Yaml(other)
     }
 }
+
+/// serde_yaml's message without the path prefix
+/// or the trailing location, which is relative
+/// to the metadata block rather than the file
+pub(crate) fn yaml_message(error: &serde_yaml::Error) -> String {
+    let message = error.to_string();
+    let message = message
+        .rfind(" at line ")
+        .map(|idx| &message[..idx])
+        .unwrap_or(&message);
+    match split_yaml_path(message) {
+        Some((_, rest)) => rest.to_string(),
+        None => message.to_string(),
+    }
+}
+
+/// The dotted path to the field serde_yaml
+/// reported an error for, combining the path
+/// prefix of the message with any missing or
+/// unknown field name
+pub(crate) fn yaml_field(error: &serde_yaml::Error) -> Option<String> {
+    let message = error.to_string();
+    let (path, rest) = match split_yaml_path(&message) {
+        Some((path, rest)) => (Some(path), rest),
+        None => (None, message.as_str()),
+    };
+    let name = ["missing field `", "unknown field `"]
+        .iter()
+        .find_map(|prefix| rest.strip_prefix(prefix))
+        .and_then(|rest| rest.split('`').next());
+    match (path, name) {
+        (Some(path), Some(name)) => Some(format!("{}.{}", path, name)),
+        (Some(path), None) => Some(path.to_string()),
+        (None, Some(name)) => Some(name.to_string()),
+        (None, None) => None,
+    }
+}
+
+fn split_yaml_path(message: &str) -> Option<(&str, &str)> {
+    let (path, rest) = message.split_once(": ")?;
+    if path.is_empty() || path.contains(' ') {
+        None
+    } else {
+        Some((path, rest))
+    }
+}
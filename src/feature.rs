@@ -0,0 +1,246 @@
+use crate::Error;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, path::Path};
+
+macro_rules! features {
+    ($($variant:ident => $name:literal,)*) => {
+        /// A feature name from test262's
+        /// `features.txt`
+        ///
+        /// The named variants are a snapshot of that
+        /// file, anything else is `Unknown`
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        pub enum Feature {
+            $(
+                #[doc = concat!("`", $name, "`")]
+                $variant,
+            )*
+            /// A feature missing from
+            /// the built in snapshot
+            Unknown(String),
+        }
+
+        impl Feature {
+            const KNOWN: &'static [&'static str] = &[$($name,)*];
+
+            /// The name as it appears
+            /// in the metadata
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $(Feature::$variant => $name,)*
+                    Feature::Unknown(name) => name,
+                }
+            }
+        }
+
+        impl From<&str> for Feature {
+            fn from(name: &str) -> Self {
+                match name {
+                    $($name => Feature::$variant,)*
+                    _ => Feature::Unknown(name.to_string()),
+                }
+            }
+        }
+    };
+}
+
+features! {
+    ArrayFromAsync => "Array.fromAsync",
+    ArrayPrototypeAt => "Array.prototype.at",
+    ArrayPrototypeFlat => "Array.prototype.flat",
+    ArrayPrototypeFlatMap => "Array.prototype.flatMap",
+    ArrayPrototypeIncludes => "Array.prototype.includes",
+    ArrayPrototypeValues => "Array.prototype.values",
+    ArrayBuffer => "ArrayBuffer",
+    Atomics => "Atomics",
+    AtomicsWaitAsync => "Atomics.waitAsync",
+    BigInt => "BigInt",
+    DataView => "DataView",
+    FinalizationRegistry => "FinalizationRegistry",
+    Float16Array => "Float16Array",
+    IsHtmlDda => "IsHTMLDDA",
+    Map => "Map",
+    ObjectFromEntries => "Object.fromEntries",
+    ObjectHasOwn => "Object.hasOwn",
+    ObjectIs => "Object.is",
+    Promise => "Promise",
+    PromiseAllSettled => "Promise.allSettled",
+    PromiseAny => "Promise.any",
+    PromisePrototypeFinally => "Promise.prototype.finally",
+    PromiseWithResolvers => "Promise.withResolvers",
+    Proxy => "Proxy",
+    Reflect => "Reflect",
+    ReflectConstruct => "Reflect.construct",
+    ReflectSet => "Reflect.set",
+    ReflectSetPrototypeOf => "Reflect.setPrototypeOf",
+    Set => "Set",
+    ShadowRealm => "ShadowRealm",
+    SharedArrayBuffer => "SharedArrayBuffer",
+    StringFromCodePoint => "String.fromCodePoint",
+    StringPrototypeAt => "String.prototype.at",
+    StringPrototypeEndsWith => "String.prototype.endsWith",
+    StringPrototypeIncludes => "String.prototype.includes",
+    StringPrototypeIsWellFormed => "String.prototype.isWellFormed",
+    StringPrototypeMatchAll => "String.prototype.matchAll",
+    StringPrototypeReplaceAll => "String.prototype.replaceAll",
+    StringPrototypeToWellFormed => "String.prototype.toWellFormed",
+    StringPrototypeTrimEnd => "String.prototype.trimEnd",
+    StringPrototypeTrimStart => "String.prototype.trimStart",
+    Symbol => "Symbol",
+    SymbolAsyncIterator => "Symbol.asyncIterator",
+    SymbolHasInstance => "Symbol.hasInstance",
+    SymbolIsConcatSpreadable => "Symbol.isConcatSpreadable",
+    SymbolIterator => "Symbol.iterator",
+    SymbolMatch => "Symbol.match",
+    SymbolMatchAll => "Symbol.matchAll",
+    SymbolPrototypeDescription => "Symbol.prototype.description",
+    SymbolReplace => "Symbol.replace",
+    SymbolSearch => "Symbol.search",
+    SymbolSpecies => "Symbol.species",
+    SymbolSplit => "Symbol.split",
+    SymbolToPrimitive => "Symbol.toPrimitive",
+    SymbolToStringTag => "Symbol.toStringTag",
+    SymbolUnscopables => "Symbol.unscopables",
+    Temporal => "Temporal",
+    TypedArray => "TypedArray",
+    TypedArrayPrototypeAt => "TypedArray.prototype.at",
+    WeakMap => "WeakMap",
+    WeakRef => "WeakRef",
+    WeakSet => "WeakSet",
+    ArrayFindFromLast => "array-find-from-last",
+    ArrayGrouping => "array-grouping",
+    ArraybufferTransfer => "arraybuffer-transfer",
+    ArrowFunction => "arrow-function",
+    AsyncFunctions => "async-functions",
+    AsyncIteration => "async-iteration",
+    Caller => "caller",
+    ChangeArrayByCopy => "change-array-by-copy",
+    Class => "class",
+    ClassFieldsPrivate => "class-fields-private",
+    ClassFieldsPublic => "class-fields-public",
+    ClassMethodsPrivate => "class-methods-private",
+    ClassStaticBlock => "class-static-block",
+    ClassStaticFieldsPrivate => "class-static-fields-private",
+    ClassStaticFieldsPublic => "class-static-fields-public",
+    ClassStaticMethodsPrivate => "class-static-methods-private",
+    CoalesceExpression => "coalesce-expression",
+    ComputedPropertyNames => "computed-property-names",
+    Const => "const",
+    CrossRealm => "cross-realm",
+    Decorators => "decorators",
+    DefaultParameters => "default-parameters",
+    DestructuringAssignment => "destructuring-assignment",
+    DestructuringBinding => "destructuring-binding",
+    DynamicImport => "dynamic-import",
+    ErrorCause => "error-cause",
+    ExplicitResourceManagement => "explicit-resource-management",
+    ExportStarAsNamespaceFromModule => "export-star-as-namespace-from-module",
+    ForOf => "for-of",
+    Generators => "generators",
+    GlobalThis => "globalThis",
+    Hashbang => "hashbang",
+    HostGcRequired => "host-gc-required",
+    ImportAttributes => "import-attributes",
+    ImportMeta => "import.meta",
+    IteratorHelpers => "iterator-helpers",
+    JsonModules => "json-modules",
+    JsonSuperset => "json-superset",
+    Let => "let",
+    LogicalAssignmentOperators => "logical-assignment-operators",
+    NewTarget => "new.target",
+    NumericSeparatorLiteral => "numeric-separator-literal",
+    ObjectRest => "object-rest",
+    ObjectSpread => "object-spread",
+    OptionalCatchBinding => "optional-catch-binding",
+    OptionalChaining => "optional-chaining",
+    RegexpDotall => "regexp-dotall",
+    RegexpLookbehind => "regexp-lookbehind",
+    RegexpMatchIndices => "regexp-match-indices",
+    RegexpNamedGroups => "regexp-named-groups",
+    RegexpUnicodePropertyEscapes => "regexp-unicode-property-escapes",
+    RegexpVFlag => "regexp-v-flag",
+    ResizableArraybuffer => "resizable-arraybuffer",
+    RestParameters => "rest-parameters",
+    SetMethods => "set-methods",
+    Spread => "spread",
+    Super => "super",
+    SymbolsAsWeakmapKeys => "symbols-as-weakmap-keys",
+    TailCallOptimization => "tail-call-optimization",
+    Template => "template",
+    TopLevelAwait => "top-level-await",
+    U180e => "u180e",
+    WellFormedJsonStringify => "well-formed-json-stringify",
+}
+
+impl std::fmt::Display for Feature {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// How a feature name is compared
+/// with the `features` of a test
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeatureMatch {
+    /// Byte for byte, as the
+    /// official suite expects
+    #[default]
+    Exact,
+    /// Ignoring ASCII case, for suites
+    /// that are inconsistent about it
+    CaseInsensitive,
+}
+
+impl FeatureMatch {
+    /// If `declared` names the
+    /// same feature as `wanted`
+    pub fn matches(&self, declared: &str, wanted: &str) -> bool {
+        match self {
+            FeatureMatch::Exact => declared == wanted,
+            FeatureMatch::CaseInsensitive => declared.eq_ignore_ascii_case(wanted),
+        }
+    }
+}
+
+/// A set of valid feature names
+/// to check tests against
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeatureSet {
+    names: HashSet<String>,
+}
+
+impl FeatureSet {
+    /// The names of every named
+    /// `Feature` variant
+    pub fn builtin() -> Self {
+        Self {
+            names: Feature::KNOWN.iter().map(|name| name.to_string()).collect(),
+        }
+    }
+
+    /// Read the features from a `features.txt`
+    /// file as found in the test262 repository
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Ok(Self::parse(&std::fs::read_to_string(path)?))
+    }
+
+    /// Parse the contents of a `features.txt`
+    /// file, one feature per line with blank
+    /// lines and `#` comments ignored
+    pub fn parse(text: &str) -> Self {
+        Self {
+            names: text
+                .lines()
+                .map(|line| line.split('#').next().unwrap_or_default().trim())
+                .filter(|line| !line.is_empty())
+                .map(str::to_string)
+                .collect(),
+        }
+    }
+
+    /// If `feature` is in this set
+    pub fn contains(&self, feature: &str) -> bool {
+        self.names.contains(feature)
+    }
+}
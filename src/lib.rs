@@ -1,91 +1,1573 @@
+use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::path::{Path, PathBuf};
-use walkdir::WalkDir;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard, OnceLock, PoisonError},
+    time::SystemTime,
+};
 
+mod builder;
+mod config;
 mod error;
-pub use error::Error;
+mod feature;
+mod prescan;
+#[cfg(feature = "junit")]
+pub mod report;
+mod scan;
+pub use builder::HarnessBuilder;
+use builder::{Filter, Transform};
+pub use config::HarnessConfig;
+pub use error::{Error, ValidationError, Warning};
+pub use feature::{Feature, FeatureMatch, FeatureSet};
+pub use prescan::FlagScan;
 
 /// The test harness
+#[derive(Debug, Clone, PartialEq)]
 pub struct Harness {
+    roots: Vec<PathBuf>,
     test_paths: Vec<PathBuf>,
+    skipped: Vec<PathBuf>,
     idx: usize,
+    transform: Option<Transform>,
+    harness_dir: Option<PathBuf>,
+    include_overrides: Arc<HashMap<String, PathBuf>>,
+    validate_includes: bool,
+    deny_duplicate_keys: bool,
+    global_includes: Arc<[String]>,
+    async_markers: Arc<AsyncMarkers>,
+    metadata_markers: Arc<MetadataMarkers>,
+    filter: Filter,
+    cache: Option<Cache>,
 }
 
 impl Harness {
     /// Provide the root path for the
     /// test directory from the test262 repository
     pub fn new<P: AsRef<Path>>(test_root: P) -> Result<Self, Error> {
-        let test_paths = WalkDir::new(test_root)
-            .min_depth(1)
+        Self::builder(test_root).build()
+    }
+
+    /// Provide more than one root path,
+    /// the tests found in each are chained
+    /// together in the order the roots
+    /// were provided
+    pub fn new_multi(roots: &[PathBuf]) -> Result<Self, Error> {
+        roots
+            .iter()
+            .fold(HarnessBuilder::default(), HarnessBuilder::root)
+            .build()
+    }
+
+    /// Construct a harness for `test_root`
+    /// with the options in `config`
+    pub fn with_config<P: AsRef<Path>>(test_root: P, config: HarnessConfig) -> Result<Self, Error> {
+        Self::builder(test_root).config(config).build()
+    }
+
+    /// Read and parse exactly one test file,
+    /// with no root its `relative_path` is
+    /// `path` as provided
+    ///
+    /// The file needs a `.js` or `.mjs`
+    /// extension
+    pub fn single<P: AsRef<Path>>(path: P) -> Result<Test, Error> {
+        let path = path.as_ref();
+        let is_test = path
+            .extension()
+            .is_some_and(|ext| ext == "js" || ext == "mjs");
+        if path.is_dir() || !is_test {
+            return Err(Error::NotATestFile(path.to_path_buf()));
+        }
+        HarnessBuilder::default()
+            .build()?
+            .create_test_from_file(path)
+    }
+
+    /// Start configuring a harness
+    /// for the provided test root
+    pub fn builder<P: AsRef<Path>>(test_root: P) -> HarnessBuilder {
+        HarnessBuilder::new(test_root)
+    }
+
+    /// Iterate over every test from the start,
+    /// regardless of how far this harness
+    /// has been advanced
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            harness: self,
+            idx: 0,
+        }
+    }
+
+    /// The path of the test that the next
+    /// call to `next` will parse, without
+    /// advancing the iterator
+    pub fn peek_path(&self) -> Option<&Path> {
+        self.test_paths.get(self.idx).map(PathBuf::as_path)
+    }
+
+    /// The index of the next path to read,
+    /// i.e. how many paths have been walked
+    ///
+    /// Paths dropped by the `HarnessConfig`
+    /// filters are counted too so this can be
+    /// more than the tests yielded, it is the
+    /// index `skip_to` takes
+    pub fn current_index(&self) -> usize {
+        self.idx
+    }
+
+    /// Move the cursor so the next path read
+    /// is the one at `idx`, either forwards or
+    /// back, e.g. to resume after a crash
+    ///
+    /// `idx` may be the number of paths, leaving
+    /// nothing to yield, anything past that is an
+    /// `Error::IndexOutOfBounds`
+    pub fn skip_to(&mut self, idx: usize) -> Result<(), Error> {
+        if idx > self.test_paths.len() {
+            return Err(Error::IndexOutOfBounds {
+                index: idx,
+                len: self.test_paths.len(),
+            });
+        }
+        self.idx = idx;
+        Ok(())
+    }
+
+    /// Drop the tests matching an entry
+    /// in `skip`
+    ///
+    /// Paths are relative to the test root and
+    /// use `/` as a separator, tests skipped by
+    /// path are never read
+    pub fn with_skip_list(
+        mut self,
+        skip: HashSet<String>,
+        matching: SkipMatch,
+    ) -> impl Iterator<Item = Result<Test, Error>> {
+        if matching != SkipMatch::Id {
+            // only the unread paths, so `idx`
+            // still points at the next one
+            let remaining = self
+                .test_paths
+                .split_off(self.idx.min(self.test_paths.len()));
+            let kept: Vec<_> = remaining
+                .into_iter()
+                .filter(|path| !skip.contains(&path_key(self.relative_to_root(path))))
+                .collect();
+            self.test_paths.extend(kept);
+        }
+        self.filter(move |test| match test {
+            Ok(test) if matching != SkipMatch::Path => {
+                !test.desc.ids().any(|(_, id)| skip.contains(id))
+            }
+            _ => true,
+        })
+    }
+
+    /// Share parsed tests through `cache`, keyed
+    /// by path, reusing an entry without reading
+    /// the file again while its modification time
+    /// is unchanged
+    ///
+    /// Entries are stored as this harness parsed
+    /// them, so a cache should only be shared by
+    /// harnesses configured the same way
+    pub fn with_cache(mut self, cache: Arc<Mutex<HashMap<PathBuf, (SystemTime, Test)>>>) -> Self {
+        self.cache = Some(Cache(cache));
+        self
+    }
+
+    /// Keep a reproducible `fraction` of the
+    /// remaining tests, e.g. `0.05` for a smoke run
+    ///
+    /// Each path relative to its root is hashed
+    /// with `seed` and kept if the hash falls in
+    /// the lowest `fraction` of the range, so the
+    /// same seed picks the same tests on any
+    /// machine and the picks are spread across
+    /// directories. The count kept is only roughly
+    /// `fraction` of the total
+    pub fn sample(mut self, fraction: f64, seed: u64) -> Self {
+        let remaining = self
+            .test_paths
+            .split_off(self.idx.min(self.test_paths.len()));
+        let sampled: Vec<_> = remaining
             .into_iter()
-            .filter_map(|e| {
-                let entry = match e {
-                    Err(e) => return Some(Err(e)),
-                    Ok(e) => e,
-                };
-                let path = entry.path();
-                if path.is_dir() {
-                    None
-                } else {
-                    let ext = path.extension()?;
-                    if ext == "js" {
-                        let file_name = path.file_name()?;
-                        let file_name = file_name.to_str()?;
-                        if file_name.ends_with("_FIXTURE.js") {
-                            None
-                        } else {
-                            Some(Ok(path.to_path_buf()))
-                        }
-                    } else {
-                        None
+            .filter(|path| {
+                let hash = sample_hash(&path_key(self.relative_to_root(path)), seed);
+                fraction >= 1.0 || (hash as f64) < fraction * u64::MAX as f64
+            })
+            .collect();
+        self.test_paths.extend(sampled);
+        self
+    }
+
+    /// The files found while walking the roots
+    /// that were ignored for not having one of
+    /// `HarnessConfig::extensions`, e.g. a
+    /// renamed `a.js.bak`, in the order found
+    ///
+    /// This is empty unless
+    /// `HarnessBuilder::collect_skipped` is set
+    pub fn skipped(&self) -> &[PathBuf] {
+        &self.skipped
+    }
+
+    /// The combined size in bytes of every
+    /// test file, this reads the file system
+    /// metadata for each path
+    pub fn total_size(&self) -> Result<u64, Error> {
+        let mut total = 0;
+        for path in &self.test_paths {
+            total += std::fs::metadata(path)?.len();
+        }
+        Ok(total)
+    }
+
+    /// Only yield the tests that list
+    /// `feature` in their `features`
+    ///
+    /// Feature names are canonical identifiers
+    /// so this is an exact, case-sensitive
+    /// match. Any errors are passed through
+    pub fn requiring_feature(self, feature: String) -> impl Iterator<Item = Result<Test, Error>> {
+        self.requiring_feature_matching(feature, FeatureMatch::Exact)
+    }
+
+    /// `requiring_feature` comparing
+    /// names as described by `matching`
+    pub fn requiring_feature_matching(
+        self,
+        feature: String,
+        matching: FeatureMatch,
+    ) -> impl Iterator<Item = Result<Test, Error>> {
+        self.filter(move |test| match test {
+            Ok(test) => test
+                .desc
+                .features
+                .iter()
+                .any(|declared| matching.matches(declared, &feature)),
+            Err(_) => true,
+        })
+    }
+
+    /// Only yield the tests that are expected to
+    /// run to completion, i.e. those without a
+    /// `negative`. Any errors are passed through
+    pub fn positives_only(self) -> impl Iterator<Item = Result<Test, Error>> {
+        self.filter(|test| match test {
+            Ok(test) => test.desc.negative.is_none(),
+            Err(_) => true,
+        })
+    }
+
+    /// Only yield the tests that list at
+    /// most `n` features, those listing more
+    /// tend to be integration tests. Any errors
+    /// are passed through
+    pub fn max_features(self, n: usize) -> impl Iterator<Item = Result<Test, Error>> {
+        self.filter(move |test| match test {
+            Ok(test) => test.desc.features.len() <= n,
+            Err(_) => true,
+        })
+    }
+
+    /// Only yield negative tests expected to
+    /// fail while parsing, i.e. with the
+    /// `parse` phase, which need no evaluation
+    /// to check. Any errors are passed through
+    pub fn parse_negatives(self) -> impl Iterator<Item = Result<Test, Error>> {
+        self.filter(|test| match test {
+            Ok(test) => matches!(
+                test.desc.negative,
+                Some(Negative {
+                    phase: Phase::Parse,
+                    ..
+                })
+            ),
+            Err(_) => true,
+        })
+    }
+
+    /// Only yield negative tests whose `type`
+    /// is exactly `kind`, e.g. `TypeError`.
+    /// Any errors are passed through
+    pub fn expecting_error(self, kind: &str) -> impl Iterator<Item = Result<Test, Error>> {
+        let kind = kind.to_string();
+        self.filter(move |test| match test {
+            Ok(test) => test
+                .desc
+                .negative
+                .as_ref()
+                .and_then(|neg| neg.kind.as_ref())
+                .map(|expected| *expected == kind)
+                .unwrap_or(false),
+            Err(_) => true,
+        })
+    }
+
+    /// Only yield negative tests expecting an
+    /// error from the harness itself, either
+    /// `Test262Error` or one of the `Test262:`
+    /// prefixed kinds. Any errors are passed through
+    pub fn expecting_test262_error(self) -> impl Iterator<Item = Result<Test, Error>> {
+        self.filter(|test| match test {
+            Ok(test) => match test.desc.negative.as_ref().and_then(Negative::error_kind) {
+                Some(ErrorKind::Test262Error) => true,
+                Some(ErrorKind::Other(kind)) => kind.starts_with("Test262:"),
+                _ => false,
+            },
+            Err(_) => true,
+        })
+    }
+
+    /// Only yield the tests that depend on
+    /// the harness file `include_name`, either
+    /// explicitly or implicitly like `assert.js`
+    /// and `sta.js`. Any errors are passed through
+    pub fn using_include(self, include_name: String) -> impl Iterator<Item = Result<Test, Error>> {
+        self.filter(move |test| match test {
+            Ok(test) => test.effective_includes().contains(&include_name),
+            Err(_) => true,
+        })
+    }
+
+    /// Drain the remaining tests collecting
+    /// the union of their effective includes
+    pub fn collect_includes(self) -> Result<BTreeSet<String>, Error> {
+        let mut includes = BTreeSet::new();
+        for test in self {
+            includes.extend(test?.effective_includes());
+        }
+        Ok(includes)
+    }
+
+    /// Drain the remaining tests collecting the
+    /// paths of those with no license header,
+    /// which test262 requires of every test
+    ///
+    /// `_FIXTURE` files are skipped without
+    /// being read even if the harness yields them
+    pub fn tests_without_license(mut self) -> Result<Vec<PathBuf>, Error> {
+        self.test_paths.drain(..self.idx.min(self.test_paths.len()));
+        self.idx = 0;
+        self.test_paths.retain(|path| {
+            !path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .is_some_and(|stem| stem.ends_with("_FIXTURE"))
+        });
+        let mut paths = Vec::new();
+        for test in self {
+            let test = test?;
+            if test.license().is_none() {
+                paths.push(test.path);
+            }
+        }
+        Ok(paths)
+    }
+
+    /// Drain the remaining tests collecting the
+    /// paths of every harness file they need,
+    /// including the dependencies of those
+    /// files, in an order they can be loaded
+    ///
+    /// A file missing from `harness_dir` is
+    /// reported as an `Error::MissingInclude`
+    /// for the first test that needs it
+    pub fn minimal_harness_set<P: AsRef<Path>>(
+        self,
+        harness_dir: P,
+    ) -> Result<Vec<PathBuf>, Error> {
+        let harness_dir = harness_dir.as_ref();
+        let overrides = self.include_overrides.clone();
+        let mut required_by: BTreeMap<String, PathBuf> = BTreeMap::new();
+        for test in self {
+            let test = test?;
+            for include in test.effective_includes() {
+                required_by
+                    .entry(include)
+                    .or_insert_with(|| test.path.clone());
+            }
+        }
+        let declared: Vec<String> = required_by.keys().cloned().collect();
+        let mut paths = Vec::new();
+        for include in order_includes(&declared, true) {
+            let path = include_path(harness_dir, &overrides, &include);
+            if !path.is_file() {
+                let test = required_by
+                    .iter()
+                    .find(|(name, _)| order_includes(&[(*name).clone()], true).contains(&include))
+                    .map(|(_, test)| test.clone())
+                    .unwrap_or_default();
+                return Err(Error::MissingInclude { test, include });
+            }
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+
+    /// Drain the remaining tests grouping those
+    /// flagged `generated` by
+    /// `Test::generator_template`, every other
+    /// test goes in the `"__handwritten__"` group
+    pub fn by_generator_template(self) -> Result<HashMap<String, Vec<Test>>, Error> {
+        let mut groups: HashMap<String, Vec<Test>> = HashMap::new();
+        for test in self {
+            let test = test?;
+            let key = test
+                .generator_template()
+                .unwrap_or_else(|| "__handwritten__".to_string());
+            groups.entry(key).or_default().push(test);
+        }
+        Ok(groups)
+    }
+
+    /// Drain the remaining tests counting how
+    /// many point at each area of the spec
+    ///
+    /// An area is the first two `.` separated
+    /// parts of `Description::spec_anchor`, so
+    /// `sec-array.prototype.flat` counts towards
+    /// `sec-array.prototype` and the ES2015 section
+    /// `22.1.3.11` towards `22.1`. Tests without an
+    /// anchor aren't counted
+    pub fn spec_section_coverage(self) -> Result<BTreeMap<String, usize>, Error> {
+        let mut coverage = BTreeMap::new();
+        for test in self {
+            let test = test?;
+            if let Some(anchor) = test.desc.spec_anchor() {
+                let area = anchor.splitn(3, '.').take(2).collect::<Vec<_>>().join(".");
+                *coverage.entry(area).or_insert(0) += 1;
+            }
+        }
+        Ok(coverage)
+    }
+
+    /// Drain the remaining tests counting how
+    /// many have a license from each year, see
+    /// `Test::license_year`, tests without a
+    /// license aren't counted
+    pub fn license_years(self) -> Result<BTreeMap<String, usize>, Error> {
+        let mut years = BTreeMap::new();
+        for test in self {
+            if let Some(year) = test?.license_year() {
+                *years.entry(year.to_string()).or_insert(0) += 1;
+            }
+        }
+        Ok(years)
+    }
+
+    /// Drain the remaining tests counting
+    /// how many times each flag appears
+    pub fn flag_histogram(self) -> Result<HashMap<Flag, usize>, Error> {
+        let mut histogram = HashMap::new();
+        for test in self {
+            for flag in test?.desc.flags {
+                *histogram.entry(flag).or_insert(0) += 1;
+            }
+        }
+        Ok(histogram)
+    }
+
+    /// Drain the remaining tests summarizing
+    /// the suite in a single pass
+    pub fn statistics(self) -> Result<SuiteStats, Error> {
+        let mut stats = SuiteStats::default();
+        for test in self {
+            let test = test?;
+            let flags = &test.desc.flags;
+            stats.total += 1;
+            stats.negative += usize::from(test.desc.negative.is_some());
+            stats.asynchronous += usize::from(flags.contains(&Flag::Async));
+            stats.module += usize::from(flags.contains(&Flag::Module));
+            stats.raw += usize::from(flags.contains(&Flag::Raw));
+            for feature in &test.desc.features {
+                *stats.features.entry(feature.clone()).or_insert(0) += 1;
+            }
+            if let Some(category) = test.category() {
+                *stats.categories.entry(category.to_string()).or_insert(0) += 1;
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Parse and check every remaining test
+    /// without stopping at the first problem
+    ///
+    /// Along with parse errors and the problems
+    /// found by `Test::validate` this reports
+    /// any `id` declared by more than one test
+    pub fn validate_all(self) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        let mut ids: HashMap<String, PathBuf> = HashMap::new();
+        for path in &self.test_paths[self.idx..] {
+            let test = match self.create_test_from_file(path) {
+                Ok(test) if !self.filter.accepts(&test) => continue,
+                Ok(test) => test,
+                Err(e) => {
+                    report.checked += 1;
+                    report.problems.push(Problem {
+                        path: path.clone(),
+                        kind: ProblemKind::Parse(e),
+                    });
+                    continue;
+                }
+            };
+            report.checked += 1;
+            if let Err(e) = test.validate() {
+                report.problems.push(Problem {
+                    path: path.clone(),
+                    kind: ProblemKind::Invalid(e),
+                });
+            }
+            if let Some(id) = &test.desc.id {
+                match ids.get(id) {
+                    Some(first) => report.problems.push(Problem {
+                        path: path.clone(),
+                        kind: ProblemKind::DuplicateId {
+                            id: id.clone(),
+                            first: first.clone(),
+                        },
+                    }),
+                    None => {
+                        ids.insert(id.clone(), path.clone());
                     }
                 }
+            }
+        }
+        report
+    }
+
+    /// Yield the tests in batches of `n`, e.g.
+    /// to hand each batch to one engine process
+    ///
+    /// Every batch holds exactly `n` items except
+    /// the last, which holds whatever is left and
+    /// is never empty. Errors take up a slot like
+    /// any test. Panics if `n` is 0
+    pub fn chunks(mut self, n: usize) -> impl Iterator<Item = Vec<Result<Test, Error>>> {
+        assert!(n != 0, "chunk size must be non-zero");
+        std::iter::from_fn(move || {
+            let mut chunk = Vec::with_capacity(n.min(self.size_hint().1.unwrap_or(n)));
+            chunk.extend(self.by_ref().take(n));
+            if chunk.is_empty() {
+                None
+            } else {
+                Some(chunk)
+            }
+        })
+    }
+
+    /// Yield each remaining test parsed as well as
+    /// possible, along with a `Warning` for each
+    /// problem that would otherwise be an error
+    ///
+    /// A field that can't be read is left empty,
+    /// unknown flags are dropped and a missing
+    /// license, unknown error type, malformed
+    /// locale or, with `validate_includes`, missing
+    /// include is only reported. A file without a
+    /// metadata block or that can't be read is
+    /// still an error. The harness' cache isn't used
+    pub fn lenient(self) -> impl Iterator<Item = Result<LenientTest, Error>> {
+        let paths = self.test_paths[self.idx.min(self.test_paths.len())..].to_vec();
+        paths.into_iter().filter_map(move |path| {
+            let mut warnings = Vec::new();
+            let test = read_source(&path).and_then(|(contents, raw)| {
+                self.build_test(&path, contents, raw, Some(&mut warnings))
+            });
+            match test {
+                Ok(test) if !self.filter.accepts(&test) => None,
+                Ok(test) => Some(Ok(LenientTest { test, warnings })),
+                Err(e) => Some(Err(e)),
+            }
+        })
+    }
+
+    /// Yield each test along with the paths of
+    /// its effective includes in `harness_dir`,
+    /// in load order
+    ///
+    /// An include that isn't a file in
+    /// `harness_dir` is yielded as an
+    /// `Error::MissingInclude` in place of the test
+    pub fn with_resolved_includes<P: AsRef<Path>>(
+        self,
+        harness_dir: P,
+    ) -> impl Iterator<Item = Result<ResolvedTest, Error>> {
+        let harness_dir = harness_dir.as_ref().to_path_buf();
+        let overrides = self.include_overrides.clone();
+        self.map(move |test| {
+            let test = test?;
+            let mut includes = Vec::new();
+            for include in test.effective_includes() {
+                let path = include_path(&harness_dir, &overrides, &include);
+                if !path.is_file() {
+                    return Err(Error::MissingInclude {
+                        test: test.path,
+                        include,
+                    });
+                }
+                includes.push(path);
+            }
+            Ok(ResolvedTest { test, includes })
+        })
+    }
+
+    /// Yield one item for every way each
+    /// test needs to be executed, as described
+    /// by `Test::execution_plan`
+    pub fn expanded(self) -> impl Iterator<Item = Result<RunnableTest, Error>> {
+        self.flat_map(|test| -> Vec<Result<RunnableTest, Error>> {
+            let test = match test {
+                Ok(test) => test,
+                Err(e) => return vec![Err(e)],
+            };
+            let mut plan = test.execution_plan();
+            let last = plan.pop();
+            let mut runs: Vec<_> = plan
+                .into_iter()
+                .map(|mode| {
+                    Ok(RunnableTest {
+                        test: test.clone(),
+                        mode,
+                    })
+                })
+                .collect();
+            runs.extend(last.map(|mode| Ok(RunnableTest { test, mode })));
+            runs
+        })
+    }
+
+    /// Read only the `flags`, `features` and
+    /// negative phase of each remaining test
+    ///
+    /// This skips deserializing the full
+    /// `Description`, for runners that shard
+    /// tests before parsing them properly. The
+    /// skipped ids and feature filters of the
+    /// `HarnessConfig` still apply
+    pub fn scan_flags(self) -> impl Iterator<Item = Result<FlagScan, Error>> {
+        let markers = self.metadata_markers;
+        let filter = self.filter;
+        self.test_paths
+            .into_iter()
+            .skip(self.idx)
+            .map(move |path| -> Result<Option<FlagScan>, Error> {
+                let (contents, _) = read_source(&path)?;
+                let (yaml_start, yaml_end) = Self::find_yaml(&contents, &path, &markers)?;
+                let yaml = &contents[yaml_start..yaml_end];
+                let scan = prescan::scan(yaml, &path)?;
+                let ids = prescan::ids(yaml);
+                Ok(Some(scan)
+                    .filter(|scan| filter.accepts_metadata(ids.iter().copied(), &scan.features)))
             })
-            .collect::<Result<Vec<PathBuf>, walkdir::Error>>()?;
-        Ok(Self { test_paths, idx: 0 })
+            .filter_map(Result::transpose)
+    }
+
+    fn create_test_from_file(&self, p: &Path) -> Result<Test, Error> {
+        let cache = match &self.cache {
+            Some(cache) => cache,
+            None => {
+                let (contents, raw) = read_source(p)?;
+                return self.create_test(p, contents, raw);
+            }
+        };
+        let modified = std::fs::metadata(p)?.modified()?;
+        if let Some((cached, test)) = cache.lock().get(p) {
+            if *cached == modified {
+                return Ok(test.clone());
+            }
+        }
+        let (contents, raw) = read_source(p)?;
+        let test = self.create_test(p, contents, raw)?;
+        cache
+            .lock()
+            .insert(p.to_path_buf(), (modified, test.clone()));
+        Ok(test)
     }
 
-    fn create_test_from(&self, p: &PathBuf) -> Result<Test, Error> {
-        let contents = std::fs::read_to_string(p)?;
-        let (yaml_start, yaml_end) = Self::find_yaml(&contents, p)?;
-        let yaml = contents[yaml_start..yaml_end].replace("\r", "\n");
-        let desc = serde_yaml::from_str(&yaml)?;
-        Ok(Test {
+    fn create_test(&self, p: &Path, contents: String, raw: Option<Vec<u8>>) -> Result<Test, Error> {
+        self.build_test(p, contents, raw, None)
+    }
+
+    /// `create_test`, recording a `Warning` for
+    /// every problem it can work around rather
+    /// than failing when given `warnings`
+    fn build_test(
+        &self,
+        p: &Path,
+        mut contents: String,
+        mut raw: Option<Vec<u8>>,
+        mut warnings: Option<&mut Vec<Warning>>,
+    ) -> Result<Test, Error> {
+        // a leading byte order mark would shift
+        // every offset found below
+        if contents.starts_with('\u{feff}') {
+            raw.get_or_insert_with(|| contents.as_bytes().to_vec());
+            contents.drain(..'\u{feff}'.len_utf8());
+        }
+        let (yaml_start, yaml_end) = Self::find_yaml(&contents, p, &self.metadata_markers)?;
+        let desc = match warnings.as_deref_mut() {
+            Some(warnings) => {
+                Self::parse_metadata_leniently(&contents[yaml_start..yaml_end], warnings)
+            }
+            None => {
+                Self::parse_metadata(&contents, yaml_start..yaml_end, p, self.deny_duplicate_keys)?
+            }
+        };
+        let license =
+            Self::find_license(&contents[..yaml_start - self.metadata_markers.open.len()]);
+        if let (None, Some(warnings)) = (&license, warnings.as_deref_mut()) {
+            warnings.push(Warning::MissingLicense);
+        }
+        let mut test = Test {
             desc,
-            path: p.clone(),
+            path: p.to_path_buf(),
+            relative_path: self.relative_to_root(p).to_path_buf(),
+            transformed_body: None,
             source: contents,
-        })
+            metadata: yaml_start..yaml_end,
+            license,
+            raw,
+            global_includes: self.global_includes.clone(),
+            include_overrides: self.include_overrides.clone(),
+            include_check: self.harness_dir.clone().filter(|_| self.validate_includes),
+            deny_duplicate_keys: self.deny_duplicate_keys,
+            async_markers: self.async_markers.clone(),
+            metadata_markers: self.metadata_markers.clone(),
+            line_starts: OnceLock::new(),
+        };
+        if let Some(transform) = &self.transform {
+            test.transformed_body = Some(transform.apply(&test.source[test.body_range()]));
+        }
+        for include in test.missing_includes() {
+            match warnings.as_deref_mut() {
+                Some(warnings) => warnings.push(Warning::MissingInclude(include)),
+                None => {
+                    return Err(Error::MissingInclude {
+                        test: test.path,
+                        include,
+                    })
+                }
+            }
+        }
+        Ok(test)
+    }
+
+    /// Deserialize the metadata block found
+    /// at `range` in `contents`
+    fn parse_metadata(
+        contents: &str,
+        range: Range<usize>,
+        p: &Path,
+        deny_duplicate_keys: bool,
+    ) -> Result<Description, Error> {
+        let yaml = contents[range.clone()]
+            .replace("\r\n", "\n")
+            .replace('\r', "\n");
+        // every test needs at least a description
+        // or an id, so nothing but whitespace and
+        // comments is a mistake
+        let empty = yaml
+            .lines()
+            .map(str::trim)
+            .all(|line| line.is_empty() || line.starts_with('#'));
+        if empty {
+            return Err(Error::EmptyMetadata {
+                path: p.to_path_buf(),
+            });
+        }
+        if deny_duplicate_keys {
+            if let Some(key) = prescan::duplicate_key(&yaml) {
+                return Err(Error::DuplicateMetadataKey {
+                    path: p.to_path_buf(),
+                    key: key.to_string(),
+                });
+            }
+        }
+        let desc: Description =
+            serde_yaml::from_str(&yaml).map_err(|e| Self::metadata_error(e, contents, range, p))?;
+        if let Some(kind) = desc.negative.as_ref().and_then(Negative::error_kind) {
+            if !kind.is_known() {
+                return Err(Error::UnknownErrorKind {
+                    path: p.to_path_buf(),
+                    kind: kind.as_str().to_string(),
+                });
+            }
+        }
+        Ok(desc)
+    }
+
+    /// Deserialize as much of the metadata
+    /// in `yaml` as possible, leaving anything
+    /// that can't be read empty with a `Warning`
+    fn parse_metadata_leniently(yaml: &str, warnings: &mut Vec<Warning>) -> Description {
+        use serde_yaml::{Mapping, Value};
+        let yaml = yaml.replace("\r\n", "\n").replace('\r', "\n");
+        let mut map = match serde_yaml::from_str(&yaml) {
+            Ok(Value::Mapping(map)) => map,
+            Ok(Value::Null) => {
+                warnings.push(Warning::EmptyMetadata);
+                Mapping::new()
+            }
+            Ok(_) => {
+                warnings.push(Warning::InvalidYaml("expected a map".to_string()));
+                Mapping::new()
+            }
+            Err(e) => {
+                warnings.push(Warning::InvalidYaml(error::yaml_message(&e)));
+                Mapping::new()
+            }
+        };
+        if let Some(Value::Sequence(flags)) = map.get_mut("flags") {
+            flags.retain(|flag| match flag.as_str() {
+                Some(name) if name.parse::<Flag>().is_err() => {
+                    warnings.push(Warning::UnknownFlag(name.to_string()));
+                    false
+                }
+                _ => true,
+            });
+        }
+        // each field on its own, so one that can't
+        // be read doesn't take the others with it
+        let keys: Vec<Value> = map.keys().cloned().collect();
+        for key in keys {
+            let mut single = Mapping::new();
+            single.insert(key.clone(), map[&key].clone());
+            if let Err(e) = serde_yaml::from_value::<Description>(Value::Mapping(single)) {
+                map.remove(&key);
+                warnings.push(Warning::InvalidField {
+                    field: key.as_str().unwrap_or_default().to_string(),
+                    message: error::yaml_message(&e),
+                });
+            }
+        }
+        let desc: Description = match serde_yaml::from_value(Value::Mapping(map)) {
+            Ok(desc) => desc,
+            Err(e) => {
+                warnings.push(Warning::InvalidYaml(error::yaml_message(&e)));
+                Description::default()
+            }
+        };
+        for locale in &desc.locale {
+            if !locale_pattern().is_match(locale) {
+                warnings.push(Warning::MalformedLocale(locale.clone()));
+            }
+        }
+        if let Some(kind) = desc.negative.as_ref().and_then(Negative::error_kind) {
+            if !kind.is_known() {
+                warnings.push(Warning::UnknownErrorKind(kind.as_str().to_string()));
+            }
+        }
+        desc
+    }
+
+    /// Attach the location of a YAML error
+    /// translated from the metadata block
+    /// into the whole file
+    fn metadata_error(
+        error: serde_yaml::Error,
+        contents: &str,
+        yaml: Range<usize>,
+        path: &Path,
+    ) -> Error {
+        let (start_line, start_column) = line_and_column(contents, yaml.start);
+        let (end_line, _) = line_and_column(contents, yaml.end);
+        let (line, column) = match error.location() {
+            Some(location) if location.line() == 1 => {
+                (start_line, start_column + location.column() - 1)
+            }
+            Some(location) => (start_line + location.line() - 1, location.column()),
+            None => (start_line, start_column),
+        };
+        let (normalized, _) = normalize_line_endings(contents);
+        let snippet = normalized
+            .lines()
+            .nth(line - 1)
+            .unwrap_or_default()
+            .trim_end()
+            .to_string();
+        let metadata = normalized
+            .lines()
+            .skip(start_line)
+            .take(end_line.saturating_sub(start_line + 1))
+            .collect::<Vec<_>>()
+            .join("\n");
+        Error::Metadata {
+            path: path.to_path_buf(),
+            line,
+            column,
+            field: error::yaml_field(&error),
+            snippet,
+            metadata,
+            error,
+        }
     }
 
-    fn find_yaml(content: &str, path: &PathBuf) -> Result<(usize, usize), Error> {
-        let start = content
-            .find("/*---")
-            .ok_or_else(|| Error::DescriptionInvalid(path.clone()))?;
-        let end = content
-            .find("---*/")
-            .ok_or_else(|| Error::DescriptionInvalid(path.clone()))?;
-        Ok((start + 5, end))
+    /// `path` relative to the longest
+    /// root that contains it
+    fn relative_to_root<'a>(&self, path: &'a Path) -> &'a Path {
+        self.roots
+            .iter()
+            .filter_map(|root| path.strip_prefix(root).ok())
+            .min_by_key(|relative| relative.components().count())
+            .unwrap_or(path)
     }
+
+    /// The range between the `/*---` and `---*/`
+    /// markers of the metadata block, or those
+    /// configured by `HarnessBuilder::metadata_delimiters`
+    ///
+    /// Either marker may also appear in a comment
+    /// or quoted in a metadata value, e.g. spec text
+    /// in `info`, so each is the first occurrence
+    /// that starts a line, falling back to the first
+    /// occurrence anywhere. The closing marker is
+    /// only searched for after the opening one
+    fn find_yaml(
+        content: &str,
+        path: &Path,
+        markers: &MetadataMarkers,
+    ) -> Result<(usize, usize), Error> {
+        let start = find_marker(content, &markers.open)
+            .ok_or_else(|| Error::DescriptionInvalid(path.to_path_buf()))?
+            + markers.open.len();
+        let end = find_marker(&content[start..], &markers.close)
+            .ok_or_else(|| Error::DescriptionInvalid(path.to_path_buf()))?;
+        Ok((start, start + end))
+    }
+
+    /// Find the license header in the region
+    /// of a file that precedes the metadata
+    ///
+    /// Line endings are normalized to `\n` before
+    /// matching so mixed `\r\n`/`\n` checkouts
+    /// behave the same, the returned range is
+    /// mapped back onto the un-normalized `header`
+    fn find_license(header: &str) -> Option<Range<usize>> {
+        let (normalized, offsets) = normalize_line_endings(header);
+        let found = license_pattern().find(&normalized)?;
+        Some(offsets[found.start()]..offsets[found.end()])
+    }
+
+    /// Every license header `find_license`
+    /// would match in `header`, in order
+    fn find_licenses(header: &str) -> Vec<Range<usize>> {
+        let (normalized, offsets) = normalize_line_endings(header);
+        license_pattern()
+            .find_iter(&normalized)
+            .map(|found| offsets[found.start()]..offsets[found.end()])
+            .collect()
+    }
+}
+
+/// What doneprintHandle.js prints when an
+/// `async` test completes or fails, see
+/// `HarnessBuilder::async_markers`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct AsyncMarkers {
+    pub(crate) pass: String,
+    pub(crate) fail_prefix: String,
+}
+
+impl Default for AsyncMarkers {
+    fn default() -> Self {
+        Self {
+            pass: "Test262:AsyncTestComplete".to_string(),
+            fail_prefix: "Test262:AsyncTestFailure:".to_string(),
+        }
+    }
+}
+
+/// The markers around the metadata
+/// block, see `HarnessBuilder::metadata_delimiters`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct MetadataMarkers {
+    pub(crate) open: String,
+    pub(crate) close: String,
+}
+
+impl Default for MetadataMarkers {
+    fn default() -> Self {
+        Self {
+            open: "/*---".to_string(),
+            close: "---*/".to_string(),
+        }
+    }
+}
+
+/// The tests shared between harnesses
+/// by `Harness::with_cache`
+#[derive(Clone)]
+struct Cache(Arc<Mutex<HashMap<PathBuf, (SystemTime, Test)>>>);
+
+impl Cache {
+    /// A panic while the lock was held can't
+    /// leave a half written entry behind, so
+    /// poisoning is ignored
+    fn lock(&self) -> MutexGuard<'_, HashMap<PathBuf, (SystemTime, Test)>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl std::fmt::Debug for Cache {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("Cache(..)")
+    }
+}
+
+impl PartialEq for Cache {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// Read the test file at `path`
+///
+/// A handful of tests deliberately contain
+/// bytes that aren't valid UTF-8, those are
+/// decoded lossily and the bytes returned
+/// alongside the text
+fn read_source(path: &Path) -> Result<(String, Option<Vec<u8>>), Error> {
+    let bytes = std::fs::read(path)?;
+    match String::from_utf8(bytes) {
+        Ok(contents) => Ok((contents, None)),
+        Err(e) => {
+            let bytes = e.into_bytes();
+            Ok((String::from_utf8_lossy(&bytes).into_owned(), Some(bytes)))
+        }
+    }
+}
+
+/// The standard harness files that
+/// rely on other harness files being
+/// loaded before them
+const HARNESS_DEPENDENCIES: &[(&str, &[&str])] = &[
+    ("asyncHelpers.js", &["assert.js", "doneprintHandle.js"]),
+    ("compareArray.js", &["assert.js"]),
+    ("compareIterator.js", &["assert.js"]),
+    ("deepEqual.js", &["assert.js"]),
+    ("nativeFunctionMatcher.js", &["assert.js"]),
+    ("promiseHelper.js", &["assert.js"]),
+    ("propertyHelper.js", &["assert.js"]),
+    ("regExpUtils.js", &["assert.js"]),
+    ("temporalHelpers.js", &["assert.js", "compareArray.js"]),
+    ("testAtomics.js", &["testTypedArray.js"]),
+    ("testBigIntTypedArray.js", &["testTypedArray.js"]),
+    ("testIntl.js", &["assert.js", "compareArray.js"]),
+    (
+        "testTypedArray.js",
+        &["assert.js", "compareArray.js", "propertyHelper.js"],
+    ),
+];
+
+/// Globals and `assert` methods defined by
+/// harness files, with the file defining each,
+/// see `Test::referenced_harness_apis`
+const HARNESS_APIS: &[(&str, &str)] = &[
+    ("assert", "assert.js"),
+    ("Test262Error", "sta.js"),
+    ("$DONOTEVALUATE", "sta.js"),
+    ("$DONE", "doneprintHandle.js"),
+    ("asyncTest", "asyncHelpers.js"),
+    ("assert.throwsAsync", "asyncHelpers.js"),
+    ("compareArray", "compareArray.js"),
+    ("assert.compareArray", "compareArray.js"),
+    ("assert.compareIterator", "compareIterator.js"),
+    ("assert.deepEqual", "deepEqual.js"),
+    ("decimalToHexString", "decimalToHexString.js"),
+    ("fnGlobalObject", "fnGlobalObject.js"),
+    ("isConstructor", "isConstructor.js"),
+    ("assertNativeFunction", "nativeFunctionMatcher.js"),
+    ("assertToStringOrNativeFunction", "nativeFunctionMatcher.js"),
+    ("validateNativeFunctionSource", "nativeFunctionMatcher.js"),
+    ("checkSequence", "promiseHelper.js"),
+    ("checkSettledPromises", "promiseHelper.js"),
+    ("verifyProperty", "propertyHelper.js"),
+    ("verifyEqualTo", "propertyHelper.js"),
+    ("verifyWritable", "propertyHelper.js"),
+    ("verifyNotWritable", "propertyHelper.js"),
+    ("verifyEnumerable", "propertyHelper.js"),
+    ("verifyNotEnumerable", "propertyHelper.js"),
+    ("verifyConfigurable", "propertyHelper.js"),
+    ("verifyNotConfigurable", "propertyHelper.js"),
+    ("buildString", "regExpUtils.js"),
+    ("testPropertyEscapes", "regExpUtils.js"),
+    ("matchValidator", "regExpUtils.js"),
+    ("TemporalHelpers", "temporalHelpers.js"),
+    (
+        "testWithAtomicsFriendlyTypedArrayConstructors",
+        "testAtomics.js",
+    ),
+    (
+        "testWithBigIntTypedArrayConstructors",
+        "testBigIntTypedArray.js",
+    ),
+    ("testWithTypedArrayConstructors", "testTypedArray.js"),
+    ("testTypedArrayConversions", "testTypedArray.js"),
+    ("TypedArrayConstructors", "testTypedArray.js"),
+];
+
+/// Where `include` is loaded from, its entry
+/// in `overrides` if there is one, otherwise
+/// the file of that name in `harness_dir`
+fn include_path(
+    harness_dir: &Path,
+    overrides: &HashMap<String, PathBuf>,
+    include: &str,
+) -> PathBuf {
+    match overrides.get(include) {
+        Some(path) => path.clone(),
+        None => harness_dir.join(include),
+    }
+}
+
+/// Sort `includes` so each file comes after
+/// its dependencies in `HARNESS_DEPENDENCIES`,
+/// with `closed` any dependency missing from
+/// `includes` is added ahead of the first file
+/// that needs it
+fn order_includes(includes: &[String], closed: bool) -> Vec<String> {
+    fn visit(
+        include: &str,
+        includes: &[String],
+        closed: bool,
+        ordered: &mut Vec<String>,
+        visiting: &mut HashSet<String>,
+    ) {
+        if ordered.iter().any(|done| done == include) || !visiting.insert(include.to_string()) {
+            return;
+        }
+        let dependencies = HARNESS_DEPENDENCIES
+            .iter()
+            .find(|(name, _)| *name == include)
+            .map(|(_, dependencies)| *dependencies)
+            .unwrap_or_default();
+        for dependency in dependencies {
+            if closed || includes.iter().any(|i| i == dependency) {
+                visit(dependency, includes, closed, ordered, visiting);
+            }
+        }
+        ordered.push(include.to_string());
+    }
+    let mut ordered = Vec::with_capacity(includes.len());
+    let mut visiting = HashSet::new();
+    for include in includes {
+        visit(include, includes, closed, &mut ordered, &mut visiting);
+    }
+    ordered
+}
+
+fn license_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(concat!(
+            r"(?i)// Copyright( \([C]\))? (\w+) .+\. {1,2}All rights reserved\.\n",
+            r"(?:// Copyright(?: \(C\))? \w+ .+\. {1,2}All rights reserved\.\n)*",
+            r"(// This code is governed by the( BSD)? license found in the LICENSE file\.",
+            r"|// See LICENSE for details\.",
+            r"|// Use of this source code is governed by a BSD-style license that can be\n",
+            r"// found in the LICENSE file\.",
+            r"|// See LICENSE or https://github\.com/tc39/test262/blob/(main|master)/LICENSE)",
+        ))
+        .expect("invalid license pattern")
+    })
+}
+
+/// The rough shape of a BCP 47 language
+/// tag, e.g. `en`, `de-DE` or `zh-Hant-TW`
+fn locale_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(r"^[A-Za-z]{2,8}(-[A-Za-z0-9]{1,8})*$").expect("invalid locale pattern")
+    })
+}
+
+/// The offset of the first `marker` at the start
+/// of a line in `text`, or of the first one
+/// anywhere if none starts a line
+fn find_marker(text: &str, marker: &str) -> Option<usize> {
+    text.match_indices(marker)
+        .map(|(idx, _)| idx)
+        .find(|&idx| idx == 0 || matches!(text.as_bytes()[idx - 1], b'\n' | b'\r'))
+        .or_else(|| text.find(marker))
+}
+
+/// The 1 based line and column of `offset`,
+/// `\r\n` counts as a single line break
+fn line_and_column(text: &str, offset: usize) -> (usize, usize) {
+    position_of(text, &line_starts(text), offset)
+}
+
+/// The byte offset each line of
+/// `text` starts at
+fn line_starts(text: &str) -> Vec<usize> {
+    let bytes = text.as_bytes();
+    let mut starts = vec![0];
+    for (idx, byte) in bytes.iter().enumerate() {
+        match byte {
+            b'\n' => starts.push(idx + 1),
+            b'\r' if bytes.get(idx + 1) != Some(&b'\n') => starts.push(idx + 1),
+            _ => {}
+        }
+    }
+    starts
+}
+
+/// The 1 based line and column of `offset`
+/// using the precomputed `line_starts` of
+/// `text`, columns are counted in characters
+fn position_of(text: &str, line_starts: &[usize], offset: usize) -> (usize, usize) {
+    let line = match line_starts.binary_search(&offset) {
+        Ok(line) => line,
+        Err(next) => next - 1,
+    };
+    let column = text[line_starts[line]..offset].chars().count() + 1;
+    (line + 1, column)
+}
+
+/// `path` as a string with `/`
+/// separating each component
+fn path_key(path: &Path) -> String {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// FNV-1a over `key` mixed with `seed`
+/// and finished with splitmix64, so similar
+/// paths land far apart
+fn sample_hash(key: &str, seed: u64) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325 ^ seed;
+    for byte in key.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    hash ^ (hash >> 31)
+}
+
+/// The value of a numeric literal, anything
+/// that doesn't parse, like a `BigInt`
+/// suffix, is treated as `0`
+fn number_value(literal: &str) -> f64 {
+    let literal = literal.replace('_', "");
+    let literal = literal.trim_end_matches('n');
+    let radix = match literal.get(..2) {
+        Some("0x") | Some("0X") => 16,
+        Some("0o") | Some("0O") => 8,
+        Some("0b") | Some("0B") => 2,
+        _ => return literal.parse().unwrap_or(0.0),
+    };
+    u64::from_str_radix(&literal[2..], radix)
+        .map(|value| value as f64)
+        .unwrap_or(f64::MAX)
+}
+
+/// `key: value` with a literal block
+/// for a value spanning several lines
+fn yaml_entry(key: &str, value: &str) -> String {
+    if value.contains(['\n', '\r']) {
+        yaml_block(key, value)
+    } else {
+        format!("{}: {}\n", key, yaml_scalar(value))
+    }
+}
+
+/// `key: |` followed by `value`
+/// indented by two spaces
+///
+/// A literal block can't hold a `\r` or a
+/// value of only line breaks, those are
+/// written double quoted instead
+fn yaml_block(key: &str, value: &str) -> String {
+    let content = value.trim_end_matches('\n');
+    if content.is_empty() || value.contains('\r') {
+        return format!("{}: {}\n", key, yaml_quoted(value));
+    }
+    let chomping = match value.len() - content.len() {
+        0 => "-",
+        1 => "",
+        _ => "+",
+    };
+    // the indentation is taken from the
+    // first line that isn't empty
+    let leading_space = content
+        .lines()
+        .find(|line| !line.is_empty())
+        .is_some_and(|line| line.starts_with([' ', '\t']));
+    let indent = if leading_space { "2" } else { "" };
+    let mut block = format!("{}: |{}{}\n", key, indent, chomping);
+    for line in value.lines() {
+        if !line.is_empty() {
+            block.push_str("  ");
+        }
+        block.push_str(line);
+        block.push('\n');
+    }
+    block
+}
+
+/// `value` as a plain scalar if YAML
+/// reads that back as the same string,
+/// otherwise single quoted
+fn yaml_scalar(value: &str) -> String {
+    let plain = !value.contains(['\n', ',', '[', ']', '{', '}'])
+        && matches!(
+            serde_yaml::from_str::<serde_yaml::Value>(value),
+            Ok(serde_yaml::Value::String(parsed)) if parsed == value
+        );
+    if plain {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', "''"))
+    }
+}
+
+/// `value` as a double quoted scalar,
+/// escaping anything YAML wouldn't read
+/// back as written
+fn yaml_quoted(value: &str) -> String {
+    let mut quoted = String::from("\"");
+    for ch in value.chars() {
+        match ch {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            ch if ch.is_control() => quoted.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => quoted.push(ch),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Remove the `.` and `..` components of
+/// `path` without touching the file system
+fn normalize_path(path: &Path) -> PathBuf {
+    use std::path::Component;
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(
+                    normalized.components().next_back(),
+                    Some(Component::Normal(_))
+                ) =>
+            {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// Replace every `\r\n` and lone `\r` with `\n`
+///
+/// Along with the normalized text this returns
+/// a table mapping each byte offset in the
+/// normalized text (plus one past the end)
+/// to the matching offset in `text`
+fn normalize_line_endings(text: &str) -> (String, Vec<usize>) {
+    let mut normalized = String::with_capacity(text.len());
+    let mut offsets = Vec::with_capacity(text.len() + 1);
+    let mut chars = text.char_indices().peekable();
+    while let Some((idx, ch)) = chars.next() {
+        if ch == '\r' {
+            if let Some((_, '\n')) = chars.peek() {
+                chars.next();
+            }
+            normalized.push('\n');
+            offsets.push(idx);
+        } else {
+            normalized.push(ch);
+            offsets.extend(std::iter::repeat_n(idx, ch.len_utf8()));
+        }
+    }
+    offsets.push(text.len());
+    (normalized, offsets)
 }
 
 impl Iterator for Harness {
     type Item = Result<Test, Error>;
     fn next(&mut self) -> Option<Self::Item> {
-        if self.idx >= self.test_paths.len() {
-            None
-        } else {
+        loop {
             let p = self.test_paths.get(self.idx)?;
             self.idx += 1;
-            Some(self.create_test_from(p))
+            match self.create_test_from_file(p) {
+                Ok(test) if !self.filter.accepts(&test) => continue,
+                result => return Some(result),
+            }
         }
     }
+
+    /// Every remaining path yields exactly one
+    /// item unless a `HarnessConfig` filter may
+    /// drop some, then only the upper bound is
+    /// known
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.test_paths.len().saturating_sub(self.idx);
+        (self.filter.lower_bound(remaining), Some(remaining))
+    }
+}
+
+/// An iterator over every test in a
+/// `Harness` that leaves the harness
+/// itself untouched
+#[derive(Debug, Clone)]
+pub struct Iter<'a> {
+    harness: &'a Harness,
+    idx: usize,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = Result<Test, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let p = self.harness.test_paths.get(self.idx)?;
+            self.idx += 1;
+            match self.harness.create_test_from_file(p) {
+                Ok(test) if !self.harness.filter.accepts(&test) => continue,
+                result => return Some(result),
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.harness.test_paths.len().saturating_sub(self.idx);
+        (self.harness.filter.lower_bound(remaining), Some(remaining))
+    }
+}
+
+impl<'a> IntoIterator for &'a Harness {
+    type Item = Result<Test, Error>;
+    type IntoIter = Iter<'a>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// A summary of a suite as
+/// returned by `Harness::statistics`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SuiteStats {
+    /// The number of tests
+    pub total: usize,
+    /// Tests with a `negative`
+    pub negative: usize,
+    /// Tests flagged `async`
+    pub asynchronous: usize,
+    /// Tests flagged `module`
+    pub module: usize,
+    /// Tests flagged `raw`
+    pub raw: usize,
+    /// How many tests list each feature
+    pub features: HashMap<String, usize>,
+    /// How many tests are in each
+    /// `Test::category`, tests directly in
+    /// a root aren't counted
+    pub categories: HashMap<String, usize>,
+}
+
+/// A rough bucket for how long
+/// a test takes to run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Cost {
+    /// A body of at most 2KiB
+    /// without any loop
+    Small,
+    /// A body over 2KiB or one with a
+    /// `for` or `while` loop
+    Medium,
+    /// A body over 16KiB or a loop alongside
+    /// a numeric literal of at least 10,000
+    Large,
+}
+
+/// Every problem found by
+/// `Harness::validate_all`
+#[derive(Debug, Default)]
+pub struct ValidationReport {
+    /// The number of tests checked
+    pub checked: usize,
+    /// The problems in the order
+    /// the tests were checked
+    pub problems: Vec<Problem>,
+}
+
+impl ValidationReport {
+    /// If no problems were found
+    pub fn is_clean(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// A problem with a single test file
+#[derive(Debug)]
+pub struct Problem {
+    /// The full path of the test file
+    pub path: PathBuf,
+    /// What is wrong with it
+    pub kind: ProblemKind,
+}
+
+/// The ways a test can fail
+/// `Harness::validate_all`
+#[derive(Debug)]
+pub enum ProblemKind {
+    /// The test couldn't be read or
+    /// its metadata couldn't be parsed
+    Parse(Error),
+    /// The test parsed but failed
+    /// `Test::validate`
+    Invalid(ValidationError),
+    /// Another test, at `first`,
+    /// already declared this `id`
+    DuplicateId {
+        /// The `id` declared twice
+        id: String,
+        /// The full path of the test
+        /// that declared it first
+        first: PathBuf,
+    },
+}
+
+/// How entries in a skip list
+/// are matched against tests
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipMatch {
+    /// Against the path relative
+    /// to the test root
+    Path,
+    /// Against any of the ids
+    /// declared in the metadata
+    Id,
+    /// Against either of the above
+    Either,
 }
 
 /// A single entry in the
 /// test suite
+#[derive(Debug, Clone)]
 pub struct Test {
     /// The full js text including the
-    /// license and metadata comments
+    /// license and metadata comments,
+    /// without any leading byte order mark
+    ///
+    /// Bytes that aren't valid UTF-8 are replaced
+    /// with U+FFFD, see `Test::raw_bytes`
     pub source: String,
     /// The full file path that this test
     /// can be found
@@ -93,11 +1575,673 @@ pub struct Test {
     /// The parsed metadata from the
     /// file
     pub desc: Description,
+    relative_path: PathBuf,
+    metadata: Range<usize>,
+    license: Option<Range<usize>>,
+    transformed_body: Option<String>,
+    /// The file's bytes when they differ
+    /// from `source`
+    raw: Option<Vec<u8>>,
+    global_includes: Arc<[String]>,
+    include_overrides: Arc<HashMap<String, PathBuf>>,
+    /// The harness directory includes are
+    /// checked against, if the harness
+    /// validates includes
+    include_check: Option<PathBuf>,
+    deny_duplicate_keys: bool,
+    async_markers: Arc<AsyncMarkers>,
+    metadata_markers: Arc<MetadataMarkers>,
+    line_starts: OnceLock<Vec<usize>>,
+}
+
+impl Test {
+    /// A key that is always present for indexing
+    /// results, the first of `id`, `esid`, `es5id`
+    /// or `es6id` declared, falling back to the
+    /// relative path with `/` separators
+    pub fn stable_id(&self) -> String {
+        match self.desc.ids().next() {
+            Some((_, id)) => id.to_string(),
+            None => path_key(&self.relative_path),
+        }
+    }
+
+    /// The 1 based line and column of a byte
+    /// `offset` into `source`, columns count
+    /// characters and `\r\n` is a single line
+    /// break
+    ///
+    /// The line index is built on first use so
+    /// each lookup after that is a binary search.
+    /// `None` if `offset` is past the end of the
+    /// source or not on a character boundary
+    pub fn to_original_position(&self, offset: usize) -> Option<(usize, usize)> {
+        if !self.source.is_char_boundary(offset) {
+            return None;
+        }
+        let line_starts = self.line_starts.get_or_init(|| line_starts(&self.source));
+        Some(position_of(&self.source, line_starts, offset))
+    }
+
+    /// The top level directory this test was
+    /// found in below its root, like `built-ins`,
+    /// `language` or `intl402`
+    pub fn category(&self) -> Option<&str> {
+        let mut components = self.relative_path.components();
+        components.next_back()?;
+        match components.next()? {
+            std::path::Component::Normal(name) => name.to_str(),
+            _ => None,
+        }
+    }
+
+    /// The directories between the test root and
+    /// this file, e.g. `built-ins/Array/prototype/flat`,
+    /// empty for a test directly in the root
+    pub fn section_path(&self) -> &Path {
+        self.relative_path.parent().unwrap_or_else(|| Path::new(""))
+    }
+
+    /// If this test needs Intl support, either
+    /// because it lives in `intl402`, declares a
+    /// `locale` or requires an `Intl` feature
+    pub fn is_intl(&self) -> bool {
+        self.category() == Some("intl402")
+            || !self.desc.locale.is_empty()
+            || self
+                .desc
+                .features
+                .iter()
+                .any(|feature| feature.starts_with("Intl"))
+    }
+
+    /// The harness files to load ahead of this
+    /// test, any `HarnessBuilder::global_includes`
+    /// followed by `Description::effective_includes`
+    ///
+    /// Global includes come before even `assert.js`
+    /// and `sta.js`, `raw` tests have none
+    pub fn effective_includes(&self) -> Vec<String> {
+        if self.desc.flags.contains(&Flag::Raw) {
+            return Vec::new();
+        }
+        let mut includes = self.global_includes.to_vec();
+        for include in self.desc.effective_includes() {
+            if !includes.contains(&include) {
+                includes.push(include);
+            }
+        }
+        includes
+    }
+
+    /// One standalone script running this test,
+    /// e.g. to attach to an engine bug report
+    ///
+    /// Each effective include is read from
+    /// `harness_dir`, or its path given to
+    /// `HarnessBuilder::override_include`, and
+    /// written ahead of the body
+    /// in dependency order, adding any dependency
+    /// that wasn't declared. An `onlyStrict` test
+    /// starts with `"use strict";`. A `raw` test
+    /// is returned as is while a module can't be
+    /// one script so is an `Error::InlineModule`
+    pub fn inline_harness(&self, harness_dir: &Path) -> Result<String, Error> {
+        if self.desc.flags.contains(&Flag::Raw) {
+            return Ok(self.body().to_string());
+        }
+        if self.run_as_module() {
+            return Err(Error::InlineModule(self.path.clone()));
+        }
+        let mut script = String::new();
+        if self.desc.flags.contains(&Flag::OnlyStrict) {
+            script.push_str("\"use strict\";\n");
+        }
+        for include in order_includes(&self.effective_includes(), true) {
+            let path = include_path(harness_dir, &self.include_overrides, &include);
+            if !path.is_file() {
+                return Err(Error::MissingInclude {
+                    test: self.path.clone(),
+                    include,
+                });
+            }
+            script.push_str(&format!("// {}\n", include));
+            script.push_str(&std::fs::read_to_string(path)?);
+            if !script.ends_with('\n') {
+                script.push('\n');
+            }
+        }
+        script.push_str(&format!("// {}\n", path_key(&self.relative_path)));
+        script.push_str(self.body());
+        Ok(script)
+    }
+
+    /// If this is a module test with an `await`
+    /// outside of any function, so evaluating
+    /// it completes asynchronously
+    ///
+    /// This is a conservative scan rather than a
+    /// parse, an `await` in a function body, a
+    /// string or a comment is never reported while
+    /// an unusual top level one may be missed
+    pub fn uses_top_level_await(&self) -> bool {
+        self.desc.flags.contains(&Flag::Module) && scan::top_level_await(self.body())
+    }
+
+    /// If this test lives in `annexB`, the
+    /// web legacy behavior that engines outside
+    /// of a browser may not implement
+    pub fn is_annex_b(&self) -> bool {
+        self.category() == Some("annexB")
+    }
+
+    /// The source text that gets executed,
+    /// `body_range` of `source` after any
+    /// `HarnessBuilder::transform`
+    pub fn body(&self) -> &str {
+        self.transformed_body
+            .as_deref()
+            .unwrap_or(&self.source[self.body_range()])
+    }
+
+    /// The offsets of the executable source
+    /// within `source`, everything after the
+    /// metadata block or for a `raw` test,
+    /// which runs exactly as written, all of it
+    pub fn body_range(&self) -> Range<usize> {
+        if self.desc.flags.contains(&Flag::Raw) {
+            0..self.source.len()
+        } else {
+            self.metadata.end + self.metadata_markers.close.len()..self.source.len()
+        }
+    }
+
+    /// If the body starts with a directive
+    /// prologue, i.e. one or more string literal
+    /// statements like `"use strict";` ahead of
+    /// any other statement
+    pub fn has_leading_directive(&self) -> bool {
+        !scan::directive_prologue(self.body()).is_empty()
+    }
+
+    /// Check that the `async` flag agrees with
+    /// the body's use of `$DONE`
+    ///
+    /// `async` tests include doneprintHandle.js
+    /// through `effective_includes`, which defines
+    /// `$DONE`, so the body needs to call it either
+    /// directly or via asyncHelpers.js' `asyncTest`.
+    /// A test that isn't `async` has no `$DONE`
+    /// defined at all
+    pub fn validate_async(&self) -> Result<(), ValidationError> {
+        let body = self.body();
+        let is_async = self.desc.flags.contains(&Flag::Async);
+        let calls_done = scan::references(body, "$DONE")
+            || (self.desc.includes.iter().any(|i| i == "asyncHelpers.js")
+                && scan::references(body, "asyncTest"));
+        match (is_async, calls_done) {
+            (true, false) => Err(ValidationError::AsyncWithoutDone),
+            (false, true) if scan::references(body, "$DONE") => {
+                Err(ValidationError::DoneWithoutAsync)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The line an `async` test prints once it
+    /// completes, `Test262:AsyncTestComplete`
+    /// unless `HarnessBuilder::async_markers`
+    /// says otherwise
+    pub fn async_pass_marker(&self) -> &str {
+        &self.async_markers.pass
+    }
+
+    /// The start of the line an `async` test
+    /// prints when it fails, followed by the
+    /// error, `Test262:AsyncTestFailure:` unless
+    /// `HarnessBuilder::async_markers` says
+    /// otherwise
+    pub fn async_fail_prefix(&self) -> &str {
+        &self.async_markers.fail_prefix
+    }
+
+    /// Check the metadata against the body and
+    /// itself, reporting the first problem found
+    ///
+    /// This covers `validate_async` and a `raw`
+    /// test also declaring `onlyStrict` or
+    /// `noStrict`
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        let flags = &self.desc.flags;
+        if flags.contains(&Flag::Raw) {
+            if let Some(flag) = flags
+                .iter()
+                .find(|flag| matches!(flag, Flag::OnlyStrict | Flag::NoStrict))
+            {
+                return Err(ValidationError::RawConflict(*flag));
+            }
+        }
+        self.validate_async()
+    }
+
+    /// The value the `[[CanBlock]]` field of the
+    /// agent record must have, if the test
+    /// requires one
+    pub fn can_block(&self) -> Option<bool> {
+        if self.desc.flags.contains(&Flag::CanBlockIsTrue) {
+            Some(true)
+        } else if self.desc.flags.contains(&Flag::CanBlockIsFalse) {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
+    /// If the body spins up worker agents with
+    /// `$262.agent.start` or messages them with
+    /// `$262.agent.broadcast`, meaning the runner
+    /// needs to provide the multi-agent harness
+    ///
+    /// The `CanBlock` flags aren't considered, they
+    /// only say what `[[CanBlock]]` the main agent
+    /// must have, see `can_block`. Plenty of tests
+    /// with one, e.g. calling `Atomics.wait` on the
+    /// main thread, never start another agent so
+    /// a runner checks both separately
+    pub fn needs_agent(&self) -> bool {
+        scan::properties_of(self.body(), &["$262", "agent"])
+            .iter()
+            .any(|name| *name == "start" || *name == "broadcast")
+    }
+
+    /// The methods of `$262.agent` the body
+    /// uses, e.g. `sleep` and `getReport`, along
+    /// with those used by the agents it starts,
+    /// in the order they first appear
+    ///
+    /// A runner can skip the tests needing a
+    /// method it doesn't provide, the agents'
+    /// source is only scanned when it's passed
+    /// directly to `$262.agent.start` as a literal
+    pub fn required_agent_apis(&self) -> Vec<String> {
+        let body = self.body();
+        let mut apis: Vec<String> = Vec::new();
+        let sources = std::iter::once(body).chain(scan::agent_sources(body));
+        for source in sources {
+            for name in scan::properties_of(source, &["$262", "agent"]) {
+                if !apis.iter().any(|api| api == name) {
+                    apis.push(name.to_string());
+                }
+            }
+        }
+        apis
+    }
+
+    /// If this test is expected to
+    /// throw an error rather than
+    /// run to completion
+    pub fn expects_throw(&self) -> bool {
+        self.desc.negative.is_some()
+    }
+
+    /// If this test has to be evaluated as a
+    /// module, see `Test::module_reason`
+    pub fn run_as_module(&self) -> bool {
+        self.module_reason().is_some()
+    }
+
+    /// Why this test is a module, the `module`
+    /// flag takes precedence over an `.mjs`
+    /// extension, which only makes a test a
+    /// module without the flag when `.mjs` files
+    /// are collected with `HarnessConfig::extensions`
+    pub fn module_reason(&self) -> Option<ModuleReason> {
+        if self.desc.flags.contains(&Flag::Module) {
+            Some(ModuleReason::Flag)
+        } else if self
+            .path
+            .extension()
+            .map(|ext| ext == "mjs")
+            .unwrap_or(false)
+        {
+            Some(ModuleReason::Extension)
+        } else {
+            None
+        }
+    }
+
+    /// If this test runs once in non-strict mode
+    /// and again in strict mode, i.e. it isn't
+    /// `raw`, a module, `onlyStrict` or `noStrict`
+    ///
+    /// This agrees with `execution_plan`, a
+    /// module can also come from an `.mjs` path
+    pub fn runs_both_modes(&self) -> bool {
+        let flags = &self.desc.flags;
+        !self.run_as_module()
+            && !flags
+                .iter()
+                .any(|flag| matches!(flag, Flag::Raw | Flag::OnlyStrict | Flag::NoStrict))
+    }
+
+    /// Each way this test needs to be run
+    ///
+    /// `raw`, module, `onlyStrict` and `noStrict`
+    /// tests run once in their respective mode,
+    /// with `raw` taking precedence, every other
+    /// test runs once in non-strict mode and again
+    /// in strict mode
+    pub fn execution_plan(&self) -> Vec<RunMode> {
+        let flags = &self.desc.flags;
+        if flags.contains(&Flag::Raw) {
+            vec![RunMode::Raw]
+        } else if self.run_as_module() {
+            vec![RunMode::Module]
+        } else if flags.contains(&Flag::OnlyStrict) {
+            vec![RunMode::Strict]
+        } else if flags.contains(&Flag::NoStrict) {
+            vec![RunMode::NonStrict]
+        } else {
+            vec![RunMode::NonStrict, RunMode::Strict]
+        }
+    }
+
+    /// How to launch an engine for each run in
+    /// `execution_plan`, two for a test that runs
+    /// in both non-strict and strict mode
+    pub fn invocation(&self) -> Vec<Invocation> {
+        let includes = self.effective_includes();
+        let is_async = self.desc.flags.contains(&Flag::Async);
+        self.execution_plan()
+            .into_iter()
+            .map(|mode| Invocation {
+                module: mode == RunMode::Module,
+                strict: mode == RunMode::Strict,
+                includes: includes.clone(),
+                is_async,
+            })
+            .collect()
+    }
+
+    /// The outcome a conforming engine
+    /// should produce for this test
+    pub fn expectation(&self) -> Expectation {
+        match &self.desc.negative {
+            Some(neg) => Expectation::Throw {
+                phase: neg.phase.clone(),
+                kind: neg.kind.clone(),
+            },
+            None => Expectation::Pass,
+        }
+    }
+
+    /// The file path relative to the
+    /// test root it was found in
+    pub fn relative_path(&self) -> &Path {
+        &self.relative_path
+    }
+
+    /// The license header comment
+    /// if one was found ahead of the
+    /// metadata, exactly as it appears
+    /// in `source`
+    pub fn license(&self) -> Option<&str> {
+        self.license
+            .as_ref()
+            .map(|range| &self.source[range.clone()])
+    }
+
+    /// The year of the copyright line of
+    /// `license`, e.g. `2019` for
+    /// `// Copyright (C) 2019 ...`
+    pub fn license_year(&self) -> Option<&str> {
+        let license = self.license()?;
+        // the year comes before the first line
+        // ending so normalizing doesn't move it
+        let (normalized, _) = normalize_line_endings(license);
+        let year = license_pattern().captures(&normalized)?.get(2)?;
+        license.get(year.range())
+    }
+
+    /// Every license header found ahead of
+    /// the metadata, the first is `license`
+    ///
+    /// Only separate headers, each with its own
+    /// line pointing at the license, are split.
+    /// Stacked copyright lines sharing one such
+    /// line, e.g. the original author's above a
+    /// generator's, are a single entry
+    pub fn licenses(&self) -> Vec<&str> {
+        let header = &self.source[..self.metadata.start - self.metadata_markers.open.len()];
+        Harness::find_licenses(header)
+            .into_iter()
+            .map(|range| &header[range])
+            .collect()
+    }
+
+    /// The `_FIXTURE.js` files this test
+    /// imports, resolved relative to the
+    /// directory the test is in
+    pub fn imported_fixtures(&self) -> Vec<PathBuf> {
+        let dir = self.path.parent().unwrap_or_else(|| Path::new(""));
+        let mut fixtures = Vec::new();
+        for specifier in scan::module_specifiers(self.body()) {
+            if !specifier.ends_with("_FIXTURE.js") {
+                continue;
+            }
+            let fixture = normalize_path(&dir.join(specifier));
+            if !fixtures.contains(&fixture) {
+                fixtures.push(fixture);
+            }
+        }
+        fixtures
+    }
+
+    /// The harness files defining the helpers
+    /// the body uses, e.g. `propertyHelper.js`
+    /// for `verifyProperty`, in the order of a
+    /// curated table of well known helpers
+    ///
+    /// A file missing from `effective_includes`
+    /// points to metadata that forgot an include
+    pub fn referenced_harness_apis(&self) -> Vec<String> {
+        let body = self.body();
+        let assert_methods = scan::properties_of(body, &["assert"]);
+        let mut files: Vec<String> = Vec::new();
+        for (api, file) in HARNESS_APIS {
+            let used = match api.strip_prefix("assert.") {
+                Some(method) => assert_methods.contains(&method),
+                None => scan::references(body, api),
+            };
+            if used && !files.iter().any(|f| f == file) {
+                files.push(file.to_string());
+            }
+        }
+        files
+    }
+
+    /// A coarse guess at how long this test
+    /// takes to run, e.g. to pick a timeout
+    ///
+    /// A body over 16KiB, or a loop alongside a
+    /// numeric literal of at least 10,000 such
+    /// as `0x10FFFF`, is `Large`. Otherwise a body
+    /// over 2KiB or any loop is `Medium` and
+    /// everything else is `Small`
+    pub fn estimated_cost(&self) -> Cost {
+        let body = self.body();
+        let mut has_loop = false;
+        let mut large_literal = false;
+        for token in scan::tokenize(body) {
+            match token.kind {
+                scan::TokenKind::Ident => {
+                    has_loop |= matches!(token.text, "for" | "while");
+                }
+                scan::TokenKind::Number => {
+                    large_literal |= number_value(token.text) >= 10_000.0;
+                }
+                _ => {}
+            }
+        }
+        if body.len() > 16 * 1024 || (has_loop && large_literal) {
+            Cost::Large
+        } else if body.len() > 2 * 1024 || has_loop {
+            Cost::Medium
+        } else {
+            Cost::Small
+        }
+    }
+
+    /// The file's contents exactly as read,
+    /// unlike `source` this keeps a byte order
+    /// mark and any bytes that aren't valid
+    /// UTF-8, for feeding to an engine as is
+    pub fn raw_bytes(&self) -> &[u8] {
+        self.raw.as_deref().unwrap_or(self.source.as_bytes())
+    }
+
+    /// The template a `generated` test was
+    /// produced from, tests generated from one
+    /// template tend to fail together
+    ///
+    /// This is the `.template` source listed in
+    /// the header the generator writes, e.g.
+    /// `src/dstr-binding/default/cls-decl-meth.template`,
+    /// falling back to the directory the test is
+    /// in relative to its root
+    pub fn generator_template(&self) -> Option<String> {
+        if !self.desc.flags.contains(&Flag::Generated) {
+            return None;
+        }
+        let header = &self.source[..self.metadata.start - self.metadata_markers.open.len()];
+        let listed = header.lines().find_map(|line| {
+            let source = line.trim().strip_prefix("//")?.trim().strip_prefix('-')?;
+            Some(source.trim()).filter(|source| source.ends_with(".template"))
+        });
+        match listed {
+            Some(template) => Some(template.to_string()),
+            None => Some(path_key(self.relative_path.parent()?)),
+        }
+    }
+
+    /// The range of `source` between the `/*---`
+    /// and `---*/` markers, i.e. the YAML text
+    /// `desc` was deserialized from
+    pub fn metadata_range(&self) -> Range<usize> {
+        self.metadata.clone()
+    }
+
+    /// Replace the text between the `/*---` and
+    /// `---*/` markers with `new_block` and
+    /// deserialize `desc` from it again, e.g. while
+    /// the metadata is being edited
+    ///
+    /// `new_block` can't contain the closing
+    /// marker and is checked the way the harness
+    /// that yielded this test checked it, see
+    /// `HarnessBuilder::deny_duplicate_keys` and
+    /// `HarnessBuilder::validate_includes`. On an
+    /// error the test is left unchanged, otherwise
+    /// `raw_bytes` returns the new `source`. The
+    /// body isn't transformed again
+    pub fn reparse_metadata(&mut self, new_block: &str) -> Result<(), Error> {
+        if new_block.contains(self.metadata_markers.close.as_str()) {
+            return Err(Error::DescriptionInvalid(self.path.clone()));
+        }
+        let mut source = self.source.clone();
+        source.replace_range(self.metadata.clone(), new_block);
+        let metadata = self.metadata.start..self.metadata.start + new_block.len();
+        let desc = Harness::parse_metadata(
+            &source,
+            metadata.clone(),
+            &self.path,
+            self.deny_duplicate_keys,
+        )?;
+        let previous = std::mem::replace(&mut self.desc, desc);
+        if let Some(include) = self.missing_includes().into_iter().next() {
+            self.desc = previous;
+            return Err(Error::MissingInclude {
+                test: self.path.clone(),
+                include,
+            });
+        }
+        self.source = source;
+        self.metadata = metadata;
+        self.raw = None;
+        self.line_starts = OnceLock::new();
+        Ok(())
+    }
+
+    /// The effective includes that aren't files,
+    /// always empty unless the harness validates
+    /// includes
+    fn missing_includes(&self) -> Vec<String> {
+        match &self.include_check {
+            Some(dir) => self
+                .effective_includes()
+                .into_iter()
+                .filter(|include| !include_path(dir, &self.include_overrides, include).is_file())
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// The comments at the very start of the
+    /// file ahead of the license, or of the
+    /// metadata if there is no license, e.g. a
+    /// `#!` line or a generator's notice
+    ///
+    /// Anything other than whitespace and
+    /// comments ends the search
+    pub fn leading_comment(&self) -> Option<&str> {
+        let end = match &self.license {
+            Some(range) => range.start,
+            None => self.metadata.start - self.metadata_markers.open.len(),
+        };
+        let header = &self.source[..end];
+        let mut rest = header;
+        let mut range: Option<Range<usize>> = None;
+        loop {
+            let trimmed = rest.trim_start();
+            let start = header.len() - trimmed.len();
+            let len = if trimmed.starts_with("//") || trimmed.starts_with("#!") {
+                trimmed.find(['\n', '\r']).unwrap_or(trimmed.len())
+            } else if let Some(block) = trimmed.strip_prefix("/*") {
+                match block.find("*/") {
+                    Some(idx) => idx + 4,
+                    None => break,
+                }
+            } else {
+                break;
+            };
+            range = Some(range.map_or(start, |r| r.start)..start + len);
+            rest = &trimmed[len..];
+        }
+        range.map(|range| &header[range])
+    }
+
+    /// `source` with only the license header
+    /// spliced out, the metadata and body are
+    /// left in place
+    pub fn without_license(&self) -> String {
+        match &self.license {
+            Some(range) => {
+                let mut source = self.source.clone();
+                source.replace_range(range.clone(), "");
+                source
+            }
+            None => self.source.clone(),
+        }
+    }
 }
 
 /// The parsed metadata from the
 /// file
-#[derive(Debug, Deserialize, Clone, Serialize, PartialEq)]
+///
+/// Every field takes part in `Eq` and `Hash`
+/// so identical metadata blocks can be
+/// deduplicated with a `HashSet`
+#[derive(Debug, Default, Deserialize, Clone, Serialize, PartialEq, Eq, Hash)]
 pub struct Description {
     /// One possible id
     pub id: Option<String>,
@@ -117,6 +2261,11 @@ pub struct Description {
     pub description: Option<String>,
     /// Will be `Some` if this
     /// test should fail
+    ///
+    /// Besides the `{phase, type}` map the
+    /// shorthand `negative: SyntaxError` is
+    /// accepted, see `Negative::from_kind`
+    #[serde(default, deserialize_with = "deserialize_negative")]
     pub negative: Option<Negative>,
     /// If this test relies on an
     /// files in the /harness
@@ -139,12 +2288,391 @@ pub struct Description {
     /// features
     #[serde(default)]
     pub features: Vec<String>,
+    /// The globals a harness file provides,
+    /// only found in the metadata of the files
+    /// in the /harness directory
+    #[serde(default)]
+    pub defines: Option<Vec<String>>,
+}
+
+impl Description {
+    /// Every file from the /harness directory
+    /// needed to run this test, in load order
+    ///
+    /// This starts with the implicit `assert.js`
+    /// and `sta.js`, followed by `doneprintHandle.js`
+    /// for `async` tests and then the declared
+    /// `includes`, a `raw` test is run as is so
+    /// it has none
+    pub fn effective_includes(&self) -> Vec<String> {
+        if self.flags.contains(&Flag::Raw) {
+            return Vec::new();
+        }
+        let mut includes = vec!["assert.js".to_string(), "sta.js".to_string()];
+        if self.flags.contains(&Flag::Async) {
+            includes.push("doneprintHandle.js".to_string());
+        }
+        for include in &self.includes {
+            if !includes.contains(include) {
+                includes.push(include.clone());
+            }
+        }
+        includes
+    }
+
+    /// One short line to show for this test,
+    /// the `description`, else the first line of
+    /// `info`, else the first declared id
+    pub fn label(&self) -> &str {
+        if let Some(description) = &self.description {
+            return description.trim();
+        }
+        if let Some(line) = self
+            .info
+            .as_deref()
+            .and_then(|info| info.lines().map(str::trim).find(|line| !line.is_empty()))
+        {
+            return line;
+        }
+        self.ids().next().map(|(_, id)| id).unwrap_or_default()
+    }
+
+    /// This metadata as a `/*---` ... `---*/`
+    /// comment, formatted the way test262 writes
+    /// it so re-parsing it produces an equal
+    /// `Description`
+    ///
+    /// Fields are written in the order `esid`,
+    /// `es5id`, `es6id`, `id`, `description`,
+    /// `info`, `negative`, `includes`, `flags`,
+    /// `features`, `locale` then `defines`,
+    /// skipping any that are empty. `info` uses a
+    /// literal block unless it holds a `\r` or
+    /// only line breaks and lists use the `[a, b]`
+    /// flow syntax
+    pub fn to_metadata_block(&self) -> String {
+        let mut block = String::from("/*---\n");
+        let scalars = [
+            ("esid", &self.esid),
+            ("es5id", &self.es5id),
+            ("es6id", &self.es6id),
+            ("id", &self.id),
+            ("description", &self.description),
+        ];
+        for (key, value) in scalars.iter() {
+            if let Some(value) = value {
+                block.push_str(&yaml_entry(key, value));
+            }
+        }
+        if let Some(info) = &self.info {
+            block.push_str(&yaml_block("info", info));
+        }
+        if let Some(negative) = &self.negative {
+            block.push_str("negative:\n");
+            block.push_str(&format!(
+                "  phase: {}\n",
+                yaml_scalar(negative.phase.as_str())
+            ));
+            if let Some(kind) = &negative.kind {
+                block.push_str(&format!("  type: {}\n", yaml_scalar(kind)));
+            }
+        }
+        let flags: Vec<String> = self.flags.iter().map(Flag::to_string).collect();
+        let lists: [(&str, &[String]); 5] = [
+            ("includes", &self.includes),
+            ("flags", &flags),
+            ("features", &self.features),
+            ("locale", &self.locale),
+            ("defines", self.defines()),
+        ];
+        for (key, items) in lists.iter() {
+            if !items.is_empty() {
+                let items: Vec<String> = items.iter().map(|item| yaml_scalar(item)).collect();
+                block.push_str(&format!("{}: [{}]\n", key, items.join(", ")));
+            }
+        }
+        block.push_str("---*/");
+        block
+    }
+
+    /// Check the metadata, as written by
+    /// `to_metadata_block`, against a JSON Schema,
+    /// e.g. to enforce a project's own policies,
+    /// listing every violation found
+    ///
+    /// Each violation starts with the JSON pointer
+    /// to the offending value, `/` for the whole
+    /// block. An invalid schema is reported as
+    /// the only violation
+    #[cfg(feature = "schema")]
+    pub fn validate_schema(&self, schema: &serde_json::Value) -> Result<(), Vec<String>> {
+        let validator = jsonschema::validator_for(schema)
+            .map_err(|e| vec![format!("invalid schema: {}", e)])?;
+        let block = self.to_metadata_block();
+        let yaml = block.trim_start_matches("/*---").trim_end_matches("---*/");
+        let value: serde_json::Value = serde_yaml::from_str(yaml)
+            .map_err(|e| vec![format!("unable to read the metadata: {}", e)])?;
+        // an empty block is an empty map
+        let value = match value {
+            serde_json::Value::Null => serde_json::Value::Object(Default::default()),
+            value => value,
+        };
+        let errors: Vec<String> = validator
+            .iter_errors(&value)
+            .map(|e| {
+                let path = e.instance_path().to_string();
+                let path = if path.is_empty() { "/" } else { &path };
+                format!("{}: {}", path, e)
+            })
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// The globals a harness file declares it
+    /// defines, empty for tests
+    pub fn defines(&self) -> &[String] {
+        self.defines.as_deref().unwrap_or_default()
+    }
+
+    /// Every declared id along with which field
+    /// it came from, in the order `id`, `esid`,
+    /// `es5id` then `es6id`
+    pub fn ids(&self) -> impl Iterator<Item = (IdKind, &str)> {
+        IntoIterator::into_iter([
+            (IdKind::Id, &self.id),
+            (IdKind::Esid, &self.esid),
+            (IdKind::Es5id, &self.es5id),
+            (IdKind::Es6id, &self.es6id),
+        ])
+        .filter_map(|(kind, id)| Some((kind, id.as_deref()?)))
+    }
+
+    /// Where in the spec this test points, the
+    /// `esid`, else the `es6id`, else the `es5id`
+    ///
+    /// An `esid` is an anchor in the current
+    /// spec, e.g. `sec-array.prototype.flat`, the
+    /// older two are section numbers of ES2015 and
+    /// ES5.1. `id` names the test itself rather
+    /// than a place in the spec so it's never used
+    pub fn spec_anchor(&self) -> Option<&str> {
+        self.esid
+            .as_deref()
+            .or(self.es6id.as_deref())
+            .or(self.es5id.as_deref())
+    }
+
+    /// `effective_includes` ordered so each
+    /// standard harness file comes after the
+    /// files it depends on
+    ///
+    /// Only dependencies already in the list are
+    /// considered, nothing is added. Includes
+    /// without a known dependency keep their
+    /// declared position
+    pub fn ordered_includes(&self) -> Vec<String> {
+        order_includes(&self.effective_includes(), false)
+    }
+
+    /// Every difference between
+    /// this and `other`
+    pub fn diff(&self, other: &Description) -> Vec<FieldChange> {
+        let mut changes = Vec::new();
+        let text_fields = [
+            ("id", &self.id, &other.id),
+            ("esid", &self.esid, &other.esid),
+            ("es5id", &self.es5id, &other.es5id),
+            ("es6id", &self.es6id, &other.es6id),
+            ("info", &self.info, &other.info),
+            ("description", &self.description, &other.description),
+        ];
+        for (field, from, to) in text_fields {
+            if from != to {
+                changes.push(FieldChange::Text {
+                    field,
+                    from: from.clone(),
+                    to: to.clone(),
+                });
+            }
+        }
+        if self.negative != other.negative {
+            changes.push(FieldChange::Negative {
+                from: self.negative.clone(),
+                to: other.negative.clone(),
+            });
+        }
+        fn list_diff<T: PartialEq + Clone>(
+            from: &[T],
+            to: &[T],
+            added: fn(T) -> FieldChange,
+            removed: fn(T) -> FieldChange,
+            changes: &mut Vec<FieldChange>,
+        ) {
+            changes.extend(
+                from.iter()
+                    .filter(|item| !to.contains(item))
+                    .cloned()
+                    .map(removed),
+            );
+            changes.extend(
+                to.iter()
+                    .filter(|item| !from.contains(item))
+                    .cloned()
+                    .map(added),
+            );
+        }
+        list_diff(
+            &self.includes,
+            &other.includes,
+            FieldChange::IncludeAdded,
+            FieldChange::IncludeRemoved,
+            &mut changes,
+        );
+        list_diff(
+            &self.flags,
+            &other.flags,
+            FieldChange::FlagAdded,
+            FieldChange::FlagRemoved,
+            &mut changes,
+        );
+        list_diff(
+            &self.locale,
+            &other.locale,
+            FieldChange::LocaleAdded,
+            FieldChange::LocaleRemoved,
+            &mut changes,
+        );
+        list_diff(
+            &self.features,
+            &other.features,
+            FieldChange::FeatureAdded,
+            FieldChange::FeatureRemoved,
+            &mut changes,
+        );
+        list_diff(
+            self.defines(),
+            other.defines(),
+            FieldChange::DefineAdded,
+            FieldChange::DefineRemoved,
+            &mut changes,
+        );
+        changes
+    }
+
+    /// The typed form of `features`
+    pub fn feature_enum(&self) -> Vec<Feature> {
+        self.features
+            .iter()
+            .map(|name| Feature::from(name.as_str()))
+            .collect()
+    }
+
+    /// The entries in `features` that are
+    /// missing from `known`, usually a typo
+    pub fn unknown_features(&self, known: &FeatureSet) -> Vec<&str> {
+        self.features
+            .iter()
+            .map(String::as_str)
+            .filter(|name| !known.contains(name))
+            .collect()
+    }
+
+    /// The `info` block formatted for display,
+    /// the indentation shared by every non-blank
+    /// line is removed along with any trailing
+    /// whitespace and leading blank lines
+    ///
+    /// Only spaces and tabs count as indentation,
+    /// other whitespace is kept as text
+    pub fn info_text(&self) -> Option<String> {
+        let info = self.info.as_ref()?;
+        // spaces and tabs are a byte each so
+        // slicing after them is always valid
+        let indent_of = |line: &str| line.len() - line.trim_start_matches([' ', '\t']).len();
+        let indent = info
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(indent_of)
+            .min()
+            .unwrap_or(0);
+        let text = info
+            .lines()
+            .skip_while(|line| line.trim().is_empty())
+            .map(|line| line[indent_of(line).min(indent)..].trim_end())
+            .collect::<Vec<_>>()
+            .join("\n");
+        Some(text.trim_end().to_string())
+    }
+}
+
+/// The metadata field an id was
+/// declared with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IdKind {
+    /// `id`
+    Id,
+    /// `esid`, an anchor in the
+    /// current specification
+    Esid,
+    /// `es5id`, a section number
+    /// in ES5
+    Es5id,
+    /// `es6id`, a section number
+    /// in ES2015
+    Es6id,
+}
+
+/// A single difference between
+/// two `Description`s
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldChange {
+    /// One of the ids, `info` or
+    /// `description` changed
+    Text {
+        /// The name of the field
+        field: &'static str,
+        /// The value in `self`
+        from: Option<String>,
+        /// The value in `other`
+        to: Option<String>,
+    },
+    /// The negative expectation changed
+    Negative {
+        /// The expectation in `self`
+        from: Option<Negative>,
+        /// The expectation in `other`
+        to: Option<Negative>,
+    },
+    /// A file was added to `includes`
+    IncludeAdded(String),
+    /// A file was removed from `includes`
+    IncludeRemoved(String),
+    /// A flag was added
+    FlagAdded(Flag),
+    /// A flag was removed
+    FlagRemoved(Flag),
+    /// A tag was added to `locale`
+    LocaleAdded(String),
+    /// A tag was removed from `locale`
+    LocaleRemoved(String),
+    /// A feature was added
+    FeatureAdded(String),
+    /// A feature was removed
+    FeatureRemoved(String),
+    /// A global was added to `defines`
+    DefineAdded(String),
+    /// A global was removed from `defines`
+    DefineRemoved(String),
 }
 
 /// If a test is expected to
 /// fail, this describes
 /// how it should fail
-#[derive(Debug, Deserialize, Clone, Serialize, PartialEq)]
+#[derive(Debug, Deserialize, Clone, Serialize, PartialEq, Eq, Hash)]
 pub struct Negative {
     /// When should this test fail
     pub phase: Phase,
@@ -154,9 +2682,249 @@ pub struct Negative {
     pub kind: Option<String>,
 }
 
+/// How a single execution of
+/// a test should be performed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RunMode {
+    /// As a script in non-strict mode
+    NonStrict,
+    /// As a script with `"use strict";`
+    /// prepended
+    Strict,
+    /// As a module
+    Module,
+    /// As a non-strict script, exactly
+    /// as the source appears with no
+    /// harness files included
+    Raw,
+}
+
+/// The options for one run of a test,
+/// see `Test::invocation`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Invocation {
+    /// Evaluate the source as a module
+    /// rather than a script
+    pub module: bool,
+    /// Prepend `"use strict";`
+    /// to the script
+    pub strict: bool,
+    /// The harness files to load
+    /// first, in order
+    pub includes: Vec<String>,
+    /// Wait for the async completion
+    /// marker before reporting a result
+    pub is_async: bool,
+}
+
+/// What makes a test a module,
+/// see `Test::module_reason`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ModuleReason {
+    /// The `module` flag
+    Flag,
+    /// An `.mjs` extension
+    /// without the flag
+    Extension,
+}
+
+/// A test parsed by `Harness::lenient`
+/// with what it had to work around
+#[derive(Debug, Clone)]
+pub struct LenientTest {
+    /// The test, with any field that
+    /// couldn't be read left empty
+    pub test: Test,
+    /// Every problem found, in the
+    /// order they were found
+    pub warnings: Vec<Warning>,
+}
+
+/// A test paired with the harness files
+/// it needs, see `Harness::with_resolved_includes`
+#[derive(Debug, Clone)]
+pub struct ResolvedTest {
+    /// The test to run
+    pub test: Test,
+    /// The path of each effective
+    /// include, in load order
+    pub includes: Vec<PathBuf>,
+}
+
+/// A test paired with one of the
+/// modes it needs to be run in
+#[derive(Debug, Clone)]
+pub struct RunnableTest {
+    /// The test to run
+    pub test: Test,
+    /// How to run it
+    pub mode: RunMode,
+}
+
+/// What a conforming engine should
+/// do when running a test
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expectation {
+    /// The test should run to completion
+    /// without an uncaught exception, for
+    /// `raw` tests that don't include the
+    /// `assert` harness this is the only
+    /// signal of success
+    Pass,
+    /// The test should throw
+    Throw {
+        /// When the error should be thrown
+        phase: Phase,
+        /// The name of the expected
+        /// exception, if declared
+        kind: Option<String>,
+    },
+}
+
+impl Negative {
+    /// The expectation for the shorthand
+    /// `negative: <type>` form, a `SyntaxError`
+    /// is expected during parsing and anything
+    /// else at runtime
+    pub fn from_kind(kind: String) -> Self {
+        let phase = if kind == "SyntaxError" {
+            Phase::Parse
+        } else {
+            Phase::Runtime
+        };
+        Self {
+            phase,
+            kind: Some(kind),
+        }
+    }
+
+    /// The typed form of `kind`
+    pub fn error_kind(&self) -> Option<ErrorKind> {
+        self.kind.as_deref().map(ErrorKind::from)
+    }
+
+    /// The name of the error to expect, filling
+    /// in an absent `kind` by convention
+    ///
+    /// INTERPRETING.md requires a `type`, but one
+    /// missing from a `parse` phase negative can
+    /// only mean a `SyntaxError`, the one error
+    /// parsing produces. In any other phase `None`
+    /// means any thrown value is acceptable
+    pub fn effective_kind(&self) -> Option<&str> {
+        match (&self.kind, &self.phase) {
+            (Some(kind), _) => Some(kind),
+            (None, Phase::Parse) => Some("SyntaxError"),
+            (None, _) => None,
+        }
+    }
+}
+
+fn deserialize_negative<'de, D>(deserializer: D) -> Result<Option<Negative>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    struct NegativeVisitor;
+    impl<'de> serde::de::Visitor<'de> for NegativeVisitor {
+        type Value = Option<Negative>;
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            f.write_str("a map with a phase and type or an error name")
+        }
+
+        fn visit_str<E: serde::de::Error>(self, kind: &str) -> Result<Self::Value, E> {
+            Ok(Some(Negative::from_kind(kind.to_string())))
+        }
+
+        fn visit_unit<E: serde::de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_map<A: serde::de::MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+            Negative::deserialize(serde::de::value::MapAccessDeserializer::new(map)).map(Some)
+        }
+    }
+    deserializer.deserialize_any(NegativeVisitor)
+}
+
+/// The constructor name of the error
+/// a negative test expects
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// A JS `SyntaxError`, e.g. for
+    /// `parse` and `resolution` tests
+    SyntaxError,
+    /// A JS `TypeError`
+    TypeError,
+    /// A JS `RangeError`
+    RangeError,
+    /// A JS `ReferenceError`
+    ReferenceError,
+    /// A JS `EvalError`
+    EvalError,
+    /// A JS `URIError`
+    URIError,
+    /// The `Test262Error` defined by
+    /// the harness file `sta.js`
+    Test262Error,
+    /// Any other name, only names
+    /// with a `Test262:` prefix are
+    /// accepted while parsing
+    Other(String),
+}
+
+impl ErrorKind {
+    /// The name as it would appear
+    /// in the metadata
+    pub fn as_str(&self) -> &str {
+        match self {
+            ErrorKind::SyntaxError => "SyntaxError",
+            ErrorKind::TypeError => "TypeError",
+            ErrorKind::RangeError => "RangeError",
+            ErrorKind::ReferenceError => "ReferenceError",
+            ErrorKind::EvalError => "EvalError",
+            ErrorKind::URIError => "URIError",
+            ErrorKind::Test262Error => "Test262Error",
+            ErrorKind::Other(name) => name,
+        }
+    }
+
+    /// If this is one of the error names
+    /// test262 uses for negative tests
+    pub fn is_known(&self) -> bool {
+        match self {
+            ErrorKind::Other(name) => name.starts_with("Test262:"),
+            _ => true,
+        }
+    }
+}
+
+impl From<&str> for ErrorKind {
+    fn from(name: &str) -> Self {
+        match name {
+            "SyntaxError" => ErrorKind::SyntaxError,
+            "TypeError" => ErrorKind::TypeError,
+            "RangeError" => ErrorKind::RangeError,
+            "ReferenceError" => ErrorKind::ReferenceError,
+            "EvalError" => ErrorKind::EvalError,
+            "URIError" => ErrorKind::URIError,
+            "Test262Error" => ErrorKind::Test262Error,
+            _ => ErrorKind::Other(name.to_string()),
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 /// Phase for negative tests
-#[derive(Debug, Deserialize, Clone, Copy, Serialize, PartialEq)]
-#[serde(rename_all = "camelCase")]
+///
+/// A phase test262 doesn't use yet is kept
+/// as `Unknown` rather than failing to parse
+#[derive(Debug, Deserialize, Clone, Serialize, PartialEq, Eq, Hash)]
+#[serde(from = "String", into = "String")]
 pub enum Phase {
     /// During the parsing step
     Parse,
@@ -167,10 +2935,80 @@ pub enum Phase {
     Resolution,
     /// During evaluation
     Runtime,
+    /// Any other phase, as written
+    Unknown(String),
 }
 
-#[derive(Debug, Deserialize, PartialEq, Clone, Copy, Serialize)]
-#[serde(rename_all = "camelCase")]
+impl Phase {
+    /// The phases an engine may actually
+    /// report this error in while still
+    /// conforming to the test's expectation
+    ///
+    /// INTERPRETING.md describes `parse` errors as
+    /// the early errors of the specification, which
+    /// must be reported before any evaluation takes
+    /// place. Some engines detect a subset of these
+    /// in a static semantics pass after producing a
+    /// syntax tree and others while parsing, so
+    /// `Parse` and `Early` are each accepted for the
+    /// other. `Resolution` and `Runtime` errors are
+    /// only conformant in their own phase
+    pub fn acceptable_actual_phases(&self) -> &[Phase] {
+        match self {
+            Phase::Parse => &[Phase::Parse, Phase::Early],
+            Phase::Early => &[Phase::Early, Phase::Parse],
+            Phase::Resolution => &[Phase::Resolution],
+            Phase::Runtime => &[Phase::Runtime],
+            Phase::Unknown(_) => std::slice::from_ref(self),
+        }
+    }
+
+    /// The name as it appears
+    /// in the metadata
+    pub fn as_str(&self) -> &str {
+        match self {
+            Phase::Parse => "parse",
+            Phase::Early => "early",
+            Phase::Resolution => "resolution",
+            Phase::Runtime => "runtime",
+            Phase::Unknown(phase) => phase,
+        }
+    }
+}
+
+impl From<String> for Phase {
+    fn from(phase: String) -> Self {
+        match phase.as_str() {
+            "parse" => Phase::Parse,
+            "early" => Phase::Early,
+            "resolution" => Phase::Resolution,
+            "runtime" => Phase::Runtime,
+            _ => Phase::Unknown(phase),
+        }
+    }
+}
+
+impl From<Phase> for String {
+    fn from(phase: Phase) -> Self {
+        match phase {
+            Phase::Unknown(phase) => phase,
+            known => known.as_str().to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for Phase {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A test262 flag, serialized with the
+/// spelling test262 uses, see `Flag::as_str`,
+/// while the camelCase forms are also accepted
+/// when deserializing
+#[derive(Debug, Deserialize, PartialEq, Eq, Hash, Clone, Copy, Serialize)]
+#[serde(try_from = "String", into = "&'static str")]
 pub enum Flag {
     /// This test should only be run in strict mode
     OnlyStrict,
@@ -185,14 +3023,68 @@ pub enum Flag {
     Async,
     /// This test was procedurally generated
     Generated,
-    /// the [[CanBlock]] record must be false
-    #[serde(alias = "CanBlockIsFalse")]
+    /// the `[[CanBlock]]` record must be false
     CanBlockIsFalse,
-    /// the [[CanBlock]] record must be true
-    #[serde(alias = "CanBlockIsTrue")]
+    /// the `[[CanBlock]]` record must be true
     CanBlockIsTrue,
     /// This test may pass in more than
     /// one way depending on implementation
-    #[serde(alias = "non-deterministic")]
     NonDeterministic,
 }
+
+impl Flag {
+    /// The name of this flag as it is
+    /// written in test262 metadata
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Flag::OnlyStrict => "onlyStrict",
+            Flag::NoStrict => "noStrict",
+            Flag::Module => "module",
+            Flag::Raw => "raw",
+            Flag::Async => "async",
+            Flag::Generated => "generated",
+            Flag::CanBlockIsFalse => "CanBlockIsFalse",
+            Flag::CanBlockIsTrue => "CanBlockIsTrue",
+            Flag::NonDeterministic => "non-deterministic",
+        }
+    }
+}
+
+impl std::str::FromStr for Flag {
+    type Err = Error;
+    /// Accepts the test262 spelling along
+    /// with the camelCase form of every flag
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "onlyStrict" => Flag::OnlyStrict,
+            "noStrict" => Flag::NoStrict,
+            "module" => Flag::Module,
+            "raw" => Flag::Raw,
+            "async" => Flag::Async,
+            "generated" => Flag::Generated,
+            "CanBlockIsFalse" | "canBlockIsFalse" => Flag::CanBlockIsFalse,
+            "CanBlockIsTrue" | "canBlockIsTrue" => Flag::CanBlockIsTrue,
+            "non-deterministic" | "nonDeterministic" => Flag::NonDeterministic,
+            _ => return Err(Error::UnknownFlag(s.to_string())),
+        })
+    }
+}
+
+impl std::fmt::Display for Flag {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::convert::TryFrom<String> for Flag {
+    type Error = Error;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Flag> for &'static str {
+    fn from(flag: Flag) -> Self {
+        flag.as_str()
+    }
+}
@@ -0,0 +1,208 @@
+//! A line based reading of the few metadata
+//! fields runners shard on, avoiding the cost
+//! of deserializing a full `Description`
+
+use crate::{Error, Flag, Negative, Phase};
+use std::path::{Path, PathBuf};
+
+/// The `flags`, `features` and negative
+/// phase of a test, as found by
+/// `Harness::scan_flags`
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlagScan {
+    /// The full path of the test file
+    pub path: PathBuf,
+    /// The `flags` as declared, empty
+    /// without the field
+    pub flags: Vec<Flag>,
+    /// The `features` as declared, empty
+    /// without the field
+    pub features: Vec<String>,
+    /// The `phase` of the `negative`
+    /// field if there is one
+    pub phase: Option<Phase>,
+}
+
+/// Extract a `FlagScan` from the text
+/// between `/*---` and `---*/`
+pub(crate) fn scan(yaml: &str, path: &Path) -> Result<FlagScan, Error> {
+    let yaml = yaml.replace("\r\n", "\n").replace('\r', "\n");
+    let mut scan = FlagScan {
+        path: path.to_path_buf(),
+        flags: Vec::new(),
+        features: Vec::new(),
+        phase: None,
+    };
+    let lines: Vec<&str> = yaml.lines().map(strip_comment).collect();
+    let mut idx = 0;
+    while idx < lines.len() {
+        let line = lines[idx];
+        idx += 1;
+        let (key, value) = match top_level_entry(line) {
+            Some(entry) => entry,
+            None => continue,
+        };
+        if !matches!(key, "flags" | "features" | "negative") {
+            continue;
+        }
+        // the lines belonging to this key are
+        // the indented ones and block sequence
+        // items that follow it
+        let nested_end = lines[idx..]
+            .iter()
+            .position(|line| top_level_entry(line).is_some())
+            .map(|offset| idx + offset)
+            .unwrap_or(lines.len());
+        let nested = &lines[idx..nested_end];
+        idx = nested_end;
+        match key {
+            "flags" => {
+                for name in sequence(value, nested) {
+                    scan.flags.push(name.parse()?);
+                }
+            }
+            "features" => {
+                scan.features = sequence(value, nested)
+                    .into_iter()
+                    .map(str::to_string)
+                    .collect();
+            }
+            _ => scan.phase = negative_phase(value, nested, path)?,
+        }
+    }
+    Ok(scan)
+}
+
+/// The first key that appears more than
+/// once at the top level of `yaml`
+pub(crate) fn duplicate_key(yaml: &str) -> Option<&str> {
+    let mut seen = Vec::new();
+    for line in yaml.lines().map(strip_comment) {
+        if let Some((key, _)) = top_level_entry(line) {
+            if seen.contains(&key) {
+                return Some(key);
+            }
+            seen.push(key);
+        }
+    }
+    None
+}
+
+/// Remove a trailing `# comment` from
+/// `line`, a `#` only starts a comment at
+/// the start of a line or after whitespace
+fn strip_comment(line: &str) -> &str {
+    let mut prev = ' ';
+    for (idx, ch) in line.char_indices() {
+        if ch == '#' && prev.is_whitespace() {
+            return &line[..idx];
+        }
+        prev = ch;
+    }
+    line
+}
+
+/// Split an un-indented `key: value`
+/// line into its key and trimmed value
+fn top_level_entry(line: &str) -> Option<(&str, &str)> {
+    if line.starts_with(char::is_whitespace) || line.starts_with('-') {
+        return None;
+    }
+    let (key, value) = line.split_once(':')?;
+    Some((unquote(key.trim()), value.trim()))
+}
+
+fn unquote(value: &str) -> &str {
+    let quoted = value.len() >= 2
+        && ((value.starts_with('"') && value.ends_with('"'))
+            || (value.starts_with('\'') && value.ends_with('\'')));
+    if quoted {
+        &value[1..value.len() - 1]
+    } else {
+        value
+    }
+}
+
+/// The items of either a flow sequence
+/// `[a, b]` possibly continued onto the
+/// `nested` lines or a block sequence of
+/// `- a` lines
+fn sequence<'a>(value: &'a str, nested: &[&'a str]) -> Vec<&'a str> {
+    let items: Vec<&str> = if value.starts_with('[') {
+        std::iter::once(value)
+            .chain(nested.iter().copied())
+            .flat_map(split_items)
+            .map(|item| item.trim().trim_start_matches('[').trim_end_matches(']'))
+            .collect()
+    } else {
+        nested
+            .iter()
+            .filter_map(|line| line.trim().strip_prefix('-'))
+            .collect()
+    };
+    items
+        .into_iter()
+        .map(|item| unquote(item.trim()))
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// Split a line of a flow sequence on the
+/// commas that aren't inside quotes
+fn split_items(line: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut quote = None;
+    let mut start = 0;
+    for (idx, ch) in line.char_indices() {
+        match (quote, ch) {
+            (None, '"' | '\'') => quote = Some(ch),
+            (Some(open), _) if open == ch => quote = None,
+            (None, ',') => {
+                items.push(&line[start..idx]);
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(&line[start..]);
+    items
+}
+
+/// The `id`, `esid`, `es5id` and `es6id`
+/// values declared at the top level of `yaml`
+pub(crate) fn ids(yaml: &str) -> Vec<&str> {
+    yaml.lines()
+        .map(strip_comment)
+        .filter_map(top_level_entry)
+        .filter(|(key, _)| matches!(*key, "id" | "esid" | "es5id" | "es6id"))
+        .map(|(_, value)| unquote(value))
+        .collect()
+}
+
+/// The phase of a `negative` given as a
+/// mapping, a flow mapping or the bare
+/// error name shorthand
+fn negative_phase(value: &str, nested: &[&str], path: &Path) -> Result<Option<Phase>, Error> {
+    if matches!(value, "" | "~" | "null") && nested.iter().all(|line| line.trim().is_empty()) {
+        return Ok(None);
+    }
+    let entries: Vec<&str> = if value.starts_with('{') {
+        value
+            .trim_start_matches('{')
+            .trim_end_matches('}')
+            .split(',')
+            .collect()
+    } else if value.is_empty() {
+        nested.to_vec()
+    } else {
+        return Ok(Some(Negative::from_kind(unquote(value).to_string()).phase));
+    };
+    for entry in entries {
+        if let Some((key, phase)) = entry.split_once(':') {
+            if unquote(key.trim()) == "phase" {
+                return Ok(Some(serde_yaml::from_str(phase.trim())?));
+            }
+        }
+    }
+    Err(Error::DescriptionInvalid(path.to_path_buf()))
+}
@@ -0,0 +1,135 @@
+//! Formatting the results of a test
+//! run for consumption by other tools
+
+use crate::Test;
+use std::fmt::Write;
+
+/// The result of running a single test
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// The test behaved as its
+    /// metadata expected
+    Pass,
+    /// The test ran but did not behave
+    /// as its metadata expected
+    Fail(String),
+    /// The test could not be run,
+    /// e.g. the engine crashed
+    Error(String),
+    /// The test was not run
+    Skip(Option<String>),
+}
+
+/// Collects `(Test, Outcome)` pairs into
+/// a JUnit XML `<testsuite>`
+///
+/// Each `<testcase>` is named by
+/// `Test::stable_id` with `Test::category`
+/// as its class name and the test's
+/// `description` as a property
+#[derive(Debug, Clone)]
+pub struct JUnitBuilder {
+    name: String,
+    cases: Vec<(Test, Outcome)>,
+}
+
+impl JUnitBuilder {
+    /// Start a suite called `name`
+    pub fn new<S: Into<String>>(name: S) -> Self {
+        Self {
+            name: name.into(),
+            cases: Vec::new(),
+        }
+    }
+
+    /// Add the outcome of one test
+    pub fn case(mut self, test: Test, outcome: Outcome) -> Self {
+        self.cases.push((test, outcome));
+        self
+    }
+
+    /// Add the outcome of every test
+    /// in `cases`
+    pub fn cases<I: IntoIterator<Item = (Test, Outcome)>>(mut self, cases: I) -> Self {
+        self.cases.extend(cases);
+        self
+    }
+
+    /// Render the suite as an XML document
+    pub fn build(&self) -> String {
+        let count = |f: fn(&Outcome) -> bool| self.cases.iter().filter(|(_, o)| f(o)).count();
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        let _ = writeln!(
+            xml,
+            "<testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" skipped=\"{}\">",
+            escape(&self.name),
+            self.cases.len(),
+            count(|o| matches!(o, Outcome::Fail(_))),
+            count(|o| matches!(o, Outcome::Error(_))),
+            count(|o| matches!(o, Outcome::Skip(_))),
+        );
+        for (test, outcome) in &self.cases {
+            let _ = writeln!(
+                xml,
+                "  <testcase name=\"{}\" classname=\"{}\">",
+                escape(&test.stable_id()),
+                escape(test.category().unwrap_or_default()),
+            );
+            if let Some(description) = &test.desc.description {
+                let _ = writeln!(
+                    xml,
+                    "    <properties>\n      <property name=\"description\" value=\"{}\"/>\n    </properties>",
+                    escape(description.trim()),
+                );
+            }
+            match outcome {
+                Outcome::Pass => {}
+                Outcome::Fail(message) => {
+                    let _ = writeln!(xml, "    {}", element("failure", message));
+                }
+                Outcome::Error(message) => {
+                    let _ = writeln!(xml, "    {}", element("error", message));
+                }
+                Outcome::Skip(None) => xml.push_str("    <skipped/>\n"),
+                Outcome::Skip(Some(reason)) => {
+                    let _ = writeln!(xml, "    <skipped message=\"{}\"/>", escape(reason));
+                }
+            }
+            xml.push_str("  </testcase>\n");
+        }
+        xml.push_str("</testsuite>\n");
+        xml
+    }
+}
+
+/// A `<failure>` or `<error>` with the first
+/// line of `message` as its attribute and
+/// the whole message as its text
+fn element(name: &str, message: &str) -> String {
+    format!(
+        "<{name} message=\"{}\">{}</{name}>",
+        escape(message.lines().next().unwrap_or_default()),
+        escape(message),
+        name = name,
+    )
+}
+
+/// Escape `text` for use in either an
+/// attribute or element, dropping the
+/// characters XML 1.0 can't represent
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(ch),
+            '\u{0}'..='\u{1f}' | '\u{fffe}' | '\u{ffff}' => {}
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
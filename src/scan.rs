@@ -0,0 +1,502 @@
+//! A small JavaScript tokenizer used to
+//! inspect test bodies without a full parser
+//!
+//! It understands comments, string, template
+//! and regular expression literals well enough
+//! that their contents never show up as code,
+//! the distinction between regular expressions
+//! and division is a heuristic on the previous
+//! token
+
+/// Punctuators longer than one
+/// character, longest first
+const PUNCTUATORS: &[&str] = &[
+    ">>>=", "...", "===", "!==", "**=", "<<=", ">>=", ">>>", "&&=", "||=", "??=", "=>", "==", "!=",
+    "<=", ">=", "&&", "||", "??", "?.", "++", "--", "+=", "-=", "*=", "/=", "%=", "&=", "|=", "^=",
+    "**", "<<", ">>",
+];
+
+/// Keywords after which a `/` starts
+/// a regular expression
+const REGEX_PREFIX_KEYWORDS: &[&str] = &[
+    "return",
+    "typeof",
+    "instanceof",
+    "in",
+    "of",
+    "new",
+    "delete",
+    "void",
+    "throw",
+    "case",
+    "do",
+    "else",
+    "yield",
+    "await",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TokenKind {
+    Ident,
+    Punct,
+    String,
+    Template,
+    Number,
+    Regex,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Token<'a> {
+    pub kind: TokenKind,
+    /// The source text of this token,
+    /// including any quotes
+    pub text: &'a str,
+    /// Byte offset into the scanned text
+    pub start: usize,
+    /// If a line terminator appeared between
+    /// this token and the previous one
+    pub newline_before: bool,
+}
+
+impl<'a> Token<'a> {
+    pub fn is_punct(&self, punct: &str) -> bool {
+        self.kind == TokenKind::Punct && self.text == punct
+    }
+
+    pub fn is_ident(&self, ident: &str) -> bool {
+        self.kind == TokenKind::Ident && self.text == ident
+    }
+
+    /// The text between the quotes of a
+    /// string literal, escapes are left as is
+    pub fn string_value(&self) -> Option<&'a str> {
+        if self.kind == TokenKind::String && self.text.len() >= 2 {
+            Some(&self.text[1..self.text.len() - 1])
+        } else {
+            None
+        }
+    }
+}
+
+pub(crate) struct Tokens<'a> {
+    text: &'a str,
+    pos: usize,
+    prev: Option<Token<'a>>,
+    /// One entry per open `{`, `true` when
+    /// it was opened by a template's `${`
+    braces: Vec<bool>,
+}
+
+pub(crate) fn tokenize(text: &str) -> Tokens<'_> {
+    Tokens {
+        text,
+        pos: 0,
+        prev: None,
+        braces: Vec::new(),
+    }
+}
+
+fn is_line_terminator(ch: char) -> bool {
+    matches!(ch, '\n' | '\r' | '\u{2028}' | '\u{2029}')
+}
+
+fn is_ident_start(ch: char) -> bool {
+    ch.is_ascii_alphabetic() || ch == '$' || ch == '_' || ch == '\\' || !ch.is_ascii()
+}
+
+fn is_ident_part(ch: char) -> bool {
+    is_ident_start(ch) || ch.is_ascii_digit()
+}
+
+impl<'a> Tokens<'a> {
+    fn rest(&self) -> &'a str {
+        &self.text[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let ch = self.peek()?;
+        self.pos += ch.len_utf8();
+        Some(ch)
+    }
+
+    /// Skip whitespace and comments returning
+    /// `true` if a line terminator was passed
+    fn skip_trivia(&mut self) -> bool {
+        let mut newline = false;
+        if self.pos == 0 && self.rest().starts_with("#!") {
+            self.skip_line();
+        }
+        while let Some(ch) = self.peek() {
+            if is_line_terminator(ch) {
+                newline = true;
+                self.bump();
+            } else if ch.is_whitespace() || ch == '\u{feff}' {
+                self.bump();
+            } else if self.rest().starts_with("//") {
+                self.skip_line();
+            } else if self.rest().starts_with("/*") {
+                let end = self.rest()[2..]
+                    .find("*/")
+                    .map(|idx| idx + 4)
+                    .unwrap_or_else(|| self.rest().len());
+                let comment = &self.rest()[..end];
+                newline |= comment.chars().any(is_line_terminator);
+                self.pos += end;
+            } else {
+                break;
+            }
+        }
+        newline
+    }
+
+    fn skip_line(&mut self) {
+        while let Some(ch) = self.peek() {
+            if is_line_terminator(ch) {
+                break;
+            }
+            self.bump();
+        }
+    }
+
+    fn regex_allowed(&self) -> bool {
+        match &self.prev {
+            None => true,
+            Some(prev) => match prev.kind {
+                TokenKind::Punct => !matches!(prev.text, ")" | "]" | "}"),
+                TokenKind::Ident => REGEX_PREFIX_KEYWORDS.contains(&prev.text),
+                _ => false,
+            },
+        }
+    }
+
+    fn scan_string(&mut self, quote: char) {
+        while let Some(ch) = self.bump() {
+            if ch == '\\' {
+                self.bump();
+            } else if ch == quote || is_line_terminator(ch) {
+                break;
+            }
+        }
+    }
+
+    /// Scan the remainder of a template
+    /// through its closing backtick or the
+    /// start of a substitution
+    fn scan_template(&mut self) {
+        while let Some(ch) = self.bump() {
+            if ch == '\\' {
+                self.bump();
+            } else if ch == '`' {
+                break;
+            } else if ch == '$' && self.peek() == Some('{') {
+                self.bump();
+                self.braces.push(true);
+                break;
+            }
+        }
+    }
+
+    /// Returns `false` without consuming
+    /// anything if there is no closing `/`
+    /// on this line
+    fn scan_regex(&mut self) -> bool {
+        let start = self.pos;
+        let mut in_class = false;
+        self.bump();
+        while let Some(ch) = self.bump() {
+            if is_line_terminator(ch) {
+                self.pos = start;
+                return false;
+            }
+            match ch {
+                '\\' => {
+                    self.bump();
+                }
+                '[' => in_class = true,
+                ']' => in_class = false,
+                '/' if !in_class => {
+                    while self.peek().map(is_ident_part).unwrap_or(false) {
+                        self.bump();
+                    }
+                    return true;
+                }
+                _ => {}
+            }
+        }
+        self.pos = start;
+        false
+    }
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Token<'a>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let newline_before = self.skip_trivia();
+        let start = self.pos;
+        let ch = self.peek()?;
+        let kind = if ch == '"' || ch == '\'' {
+            self.bump();
+            self.scan_string(ch);
+            TokenKind::String
+        } else if ch == '`' {
+            self.bump();
+            self.scan_template();
+            TokenKind::Template
+        } else if ch == '}' && self.braces.last() == Some(&true) {
+            self.braces.pop();
+            self.bump();
+            self.scan_template();
+            TokenKind::Template
+        } else if ch.is_ascii_digit()
+            || (ch == '.' && self.rest()[1..].starts_with(|c: char| c.is_ascii_digit()))
+        {
+            while self
+                .peek()
+                .map(|c| c.is_ascii_alphanumeric() || c == '.' || c == '_')
+                .unwrap_or(false)
+            {
+                self.bump();
+            }
+            TokenKind::Number
+        } else if is_ident_start(ch) {
+            while self.peek().map(is_ident_part).unwrap_or(false) {
+                self.bump();
+            }
+            TokenKind::Ident
+        } else if ch == '/' && self.regex_allowed() && self.scan_regex() {
+            TokenKind::Regex
+        } else {
+            let len = PUNCTUATORS
+                .iter()
+                .find(|p| self.rest().starts_with(**p))
+                .map(|p| p.len())
+                .unwrap_or_else(|| ch.len_utf8());
+            self.pos += len;
+            match ch {
+                '{' => self.braces.push(false),
+                '}' => {
+                    self.braces.pop();
+                }
+                _ => {}
+            }
+            TokenKind::Punct
+        };
+        let token = Token {
+            kind,
+            text: &self.text[start..self.pos],
+            start,
+            newline_before,
+        };
+        self.prev = Some(token);
+        Some(token)
+    }
+}
+
+/// If `ident` appears as an identifier
+/// anywhere outside of comments and strings
+pub(crate) fn references(text: &str, ident: &str) -> bool {
+    tokenize(text).any(|token| token.is_ident(ident))
+}
+
+/// The names of every property accessed
+/// with `.` directly on the member chain
+/// `object`, e.g. `["$262", "agent"]` finds
+/// `start` in `$262.agent.start(...)`
+pub(crate) fn properties_of<'a>(text: &'a str, object: &[&str]) -> Vec<&'a str> {
+    let tokens: Vec<_> = tokenize(text).collect();
+    let chain = object.len() * 2 + 1;
+    let is_dot = |token: &Token<'_>| token.is_punct(".") || token.is_punct("?.");
+    let mut names = Vec::new();
+    for (idx, window) in tokens.windows(chain).enumerate() {
+        // `a.$262.agent` is not `$262.agent`
+        if idx > 0 && is_dot(&tokens[idx - 1]) {
+            continue;
+        }
+        let matches = object
+            .iter()
+            .enumerate()
+            .all(|(i, name)| window[i * 2].is_ident(name) && is_dot(&window[i * 2 + 1]));
+        let last = &window[chain - 1];
+        if matches && last.kind == TokenKind::Ident && !names.contains(&last.text) {
+            names.push(last.text);
+        }
+    }
+    names
+}
+
+/// The source of every agent started with
+/// `$262.agent.start("...")`, when it is a
+/// string or template literal, without its
+/// quotes and escapes left as is
+///
+/// Only the text ahead of a template's first
+/// `${` is included
+pub(crate) fn agent_sources(text: &str) -> Vec<&str> {
+    let tokens: Vec<_> = tokenize(text).collect();
+    let mut sources = Vec::new();
+    for (idx, window) in tokens.windows(7).enumerate() {
+        if idx > 0 && tokens[idx - 1].is_punct(".") {
+            continue;
+        }
+        let starts = window[0].is_ident("$262")
+            && window[1].is_punct(".")
+            && window[2].is_ident("agent")
+            && window[3].is_punct(".")
+            && window[4].is_ident("start")
+            && window[5].is_punct("(");
+        if !starts {
+            continue;
+        }
+        let source = &window[6];
+        let inner = match source.kind {
+            TokenKind::String => source.string_value(),
+            TokenKind::Template => source
+                .text
+                .strip_prefix('`')
+                .and_then(|rest| rest.strip_suffix('`').or_else(|| rest.strip_suffix("${"))),
+            _ => None,
+        };
+        sources.extend(inner);
+    }
+    sources
+}
+
+/// The module specifiers of every static
+/// `import`/`export ... from`, side effect
+/// `import "..."` and `import("...")` with a
+/// string literal, in source order
+pub(crate) fn module_specifiers(text: &str) -> Vec<&str> {
+    let tokens: Vec<_> = tokenize(text).collect();
+    let mut specifiers = Vec::new();
+    for (idx, token) in tokens.iter().enumerate() {
+        let specifier = if token.is_ident("from") || token.is_ident("import") {
+            match tokens.get(idx + 1) {
+                Some(next) if next.is_punct("(") && token.is_ident("import") => {
+                    tokens.get(idx + 2).and_then(Token::string_value)
+                }
+                Some(next) => next.string_value(),
+                None => None,
+            }
+        } else {
+            None
+        };
+        specifiers.extend(specifier);
+    }
+    specifiers
+}
+
+/// Keywords whose parenthesized head is
+/// followed by a block rather than a
+/// function body
+const BLOCK_HEADS: &[&str] = &["if", "while", "for", "switch", "catch", "with"];
+
+/// If an `await` appears outside of every
+/// function body
+///
+/// A `{` following `=>` or the `)` of a
+/// parameter list is taken to open a function.
+/// To avoid false positives an `await` after
+/// an arrow in the same statement is ignored,
+/// as it may be in a concise arrow body
+pub(crate) fn top_level_await(text: &str) -> bool {
+    let tokens: Vec<_> = tokenize(text).collect();
+    // one entry per open `{`, `true` for
+    // a function body
+    let mut braces: Vec<bool> = Vec::new();
+    let mut parens: Vec<usize> = Vec::new();
+    let mut last_paren = None;
+    let mut after_arrow = false;
+    for (idx, token) in tokens.iter().enumerate() {
+        let prev = idx.checked_sub(1).map(|prev| &tokens[prev]);
+        let in_function = braces.iter().any(|function| *function);
+        if token.is_punct("(") {
+            parens.push(idx);
+        } else if token.is_punct(")") {
+            last_paren = parens.pop();
+        } else if token.is_punct("{") {
+            let function = match prev {
+                Some(prev) if prev.is_punct("=>") => true,
+                Some(prev) if prev.is_punct(")") => match last_paren {
+                    Some(open) if open > 0 => {
+                        let head = &tokens[open - 1];
+                        let for_await =
+                            head.is_ident("await") && open > 1 && tokens[open - 2].is_ident("for");
+                        !(for_await
+                            || (head.kind == TokenKind::Ident && BLOCK_HEADS.contains(&head.text)))
+                    }
+                    _ => false,
+                },
+                _ => false,
+            };
+            braces.push(function);
+        } else if token.is_punct("}") {
+            braces.pop();
+            if !in_function {
+                after_arrow = false;
+            }
+        } else if token.is_punct(";") && !in_function {
+            after_arrow = false;
+        } else if token.is_punct("=>") && !in_function {
+            after_arrow = true;
+        } else if token.is_ident("await") && !in_function && !after_arrow {
+            let property = prev
+                .map(|prev| prev.is_punct(".") || prev.is_punct("?."))
+                .unwrap_or(false);
+            let key = tokens
+                .get(idx + 1)
+                .map(|next| next.is_punct(":"))
+                .unwrap_or(false);
+            if !property && !key {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+/// Punctuators that continue an expression
+/// onto the next line, preventing automatic
+/// semicolon insertion after a string literal
+const CONTINUATIONS: &[&str] = &[
+    ".", "?.", "[", "(", ",", "?", ":", "=", "==", "===", "!=", "!==", "<", ">", "<=", ">=", "+",
+    "-", "*", "/", "%", "**", "<<", ">>", ">>>", "&", "|", "^", "&&", "||", "??", "+=", "-=", "*=",
+    "/=", "%=", "**=", "<<=", ">>=", ">>>=", "&=", "|=", "^=", "&&=", "||=", "??=", "=>",
+];
+
+/// The string literals that make up the
+/// directive prologue at the start of `text`
+pub(crate) fn directive_prologue(text: &str) -> Vec<&str> {
+    let mut directives = Vec::new();
+    let mut tokens = tokenize(text).peekable();
+    while let Some(token) = tokens.peek().copied() {
+        let value = match token.string_value() {
+            Some(value) => value,
+            None => break,
+        };
+        tokens.next();
+        let terminated = match tokens.peek() {
+            None => true,
+            Some(next) if next.is_punct(";") => {
+                tokens.next();
+                true
+            }
+            Some(next) if next.is_punct("}") => true,
+            Some(next) if next.newline_before => match next.kind {
+                TokenKind::Punct => !CONTINUATIONS.contains(&next.text),
+                TokenKind::Template => false,
+                TokenKind::Ident => !matches!(next.text, "in" | "instanceof"),
+                _ => true,
+            },
+            Some(_) => false,
+        };
+        if !terminated {
+            break;
+        }
+        directives.push(value);
+    }
+    directives
+}
@@ -0,0 +1,476 @@
+use super::support::{only_test, with_meta, TestDir};
+use test262_harness::{
+    Description, ErrorKind, Feature, FeatureSet, FieldChange, Flag, Negative, Phase,
+};
+
+#[test]
+fn acceptable_actual_phases() {
+    assert_eq!(
+        Phase::Parse.acceptable_actual_phases(),
+        [Phase::Parse, Phase::Early]
+    );
+    assert_eq!(
+        Phase::Early.acceptable_actual_phases(),
+        [Phase::Early, Phase::Parse]
+    );
+    assert_eq!(
+        Phase::Resolution.acceptable_actual_phases(),
+        [Phase::Resolution]
+    );
+    assert_eq!(Phase::Runtime.acceptable_actual_phases(), [Phase::Runtime]);
+}
+
+fn desc_from(yaml: &str) -> Description {
+    serde_yaml::from_str(yaml).unwrap()
+}
+
+#[test]
+fn info_text() {
+    let desc =
+        desc_from("info: |\n    1. Let x be ToNumber(y).\n      a. Return x.\n\n    2. Done.   \n");
+    assert_eq!(
+        desc.info_text().unwrap(),
+        "1. Let x be ToNumber(y).\n  a. Return x.\n\n2. Done."
+    );
+    let desc = desc_from("info: \"  a\\n\\u3000b\\n\"");
+    assert_eq!(desc.info_text().unwrap(), "  a\n\u{3000}b");
+    let desc = desc_from("info: \"\\t  a\\n\\t  \\u3000b\\n\"");
+    assert_eq!(desc.info_text().unwrap(), "a\n\u{3000}b");
+    assert_eq!(desc_from("description: no info").info_text(), None);
+}
+
+#[test]
+fn effective_includes() {
+    let desc = desc_from("includes: [compareArray.js, assert.js]");
+    assert_eq!(
+        desc.effective_includes(),
+        ["assert.js", "sta.js", "compareArray.js"]
+    );
+    let desc = desc_from("flags: [async]\nincludes: [asyncHelpers.js]");
+    assert_eq!(
+        desc.effective_includes(),
+        [
+            "assert.js",
+            "sta.js",
+            "doneprintHandle.js",
+            "asyncHelpers.js"
+        ]
+    );
+    let desc = desc_from("flags: [raw, async]\nincludes: [fnGlobalObject.js]");
+    assert!(desc.effective_includes().is_empty());
+}
+
+#[test]
+fn flag_round_trip() {
+    let flags = [
+        ("onlyStrict", Flag::OnlyStrict),
+        ("noStrict", Flag::NoStrict),
+        ("module", Flag::Module),
+        ("raw", Flag::Raw),
+        ("async", Flag::Async),
+        ("generated", Flag::Generated),
+        ("CanBlockIsFalse", Flag::CanBlockIsFalse),
+        ("CanBlockIsTrue", Flag::CanBlockIsTrue),
+        ("non-deterministic", Flag::NonDeterministic),
+    ];
+    for (name, flag) in flags {
+        assert_eq!(name.parse::<Flag>().unwrap(), flag);
+        assert_eq!(flag.to_string(), name);
+        let yaml: Vec<Flag> = serde_yaml::from_str(&format!("[{}]", name)).unwrap();
+        assert_eq!(yaml, [flag]);
+        let serialized = serde_yaml::to_string(&flag).unwrap();
+        assert_eq!(serialized.trim(), name);
+        assert_eq!(serde_yaml::from_str::<Flag>(&serialized).unwrap(), flag);
+    }
+    let desc = Description {
+        flags: flags.iter().map(|(_, flag)| *flag).collect(),
+        ..Description::default()
+    };
+    let serialized = serde_yaml::to_string(&desc).unwrap();
+    assert_eq!(
+        serde_yaml::from_str::<Description>(&serialized).unwrap(),
+        desc
+    );
+    let aliases = [
+        ("canBlockIsFalse", Flag::CanBlockIsFalse),
+        ("canBlockIsTrue", Flag::CanBlockIsTrue),
+        ("nonDeterministic", Flag::NonDeterministic),
+    ];
+    for (name, flag) in aliases {
+        assert_eq!(name.parse::<Flag>().unwrap(), flag);
+        let yaml: Vec<Flag> = serde_yaml::from_str(&format!("[{}]", name)).unwrap();
+        assert_eq!(yaml, [flag]);
+    }
+    assert!("OnlyStrict".parse::<Flag>().is_err());
+    assert!(serde_yaml::from_str::<Vec<Flag>>("[notAFlag]").is_err());
+}
+
+#[test]
+fn error_kind() {
+    let desc = desc_from("negative:\n  phase: runtime\n  type: TypeError");
+    let neg = desc.negative.unwrap();
+    assert_eq!(neg.error_kind(), Some(ErrorKind::TypeError));
+    assert_eq!(ErrorKind::from("URIError"), ErrorKind::URIError);
+    assert!(ErrorKind::from("Test262:AsyncTestFailure").is_known());
+    assert!(!ErrorKind::from("SyntaxErorr").is_known());
+    assert_eq!(ErrorKind::from("Foo.Bar").as_str(), "Foo.Bar");
+}
+
+#[test]
+fn effective_kind() {
+    let cases = [
+        (Phase::Parse, None, Some("SyntaxError")),
+        (Phase::Parse, Some("ReferenceError"), Some("ReferenceError")),
+        (Phase::Runtime, Some("TypeError"), Some("TypeError")),
+        (Phase::Runtime, None, None),
+        (Phase::Resolution, None, None),
+    ];
+    for (phase, kind, expected) in cases {
+        let neg = Negative {
+            phase: phase.clone(),
+            kind: kind.map(String::from),
+        };
+        assert_eq!(neg.effective_kind(), expected, "{} {:?}", phase, kind);
+    }
+}
+
+#[test]
+fn feature_enum() {
+    let desc = desc_from("features: [BigInt, Symbol.asyncIterator, bigint]");
+    assert_eq!(
+        desc.feature_enum(),
+        [
+            Feature::BigInt,
+            Feature::SymbolAsyncIterator,
+            Feature::Unknown("bigint".to_string())
+        ]
+    );
+    assert_eq!(desc.unknown_features(&FeatureSet::builtin()), ["bigint"]);
+    let known = FeatureSet::parse("# comment\nBigInt\n\nbigint # lowercase\n");
+    assert_eq!(desc.unknown_features(&known), ["Symbol.asyncIterator"]);
+}
+
+#[test]
+fn diff() {
+    let before = desc_from(
+        "esid: sec-old\nnegative:\n  phase: parse\n  type: SyntaxError\nfeatures: [BigInt, class]",
+    );
+    let after = desc_from(
+        "esid: sec-new\nnegative:\n  phase: runtime\n  type: SyntaxError\nfeatures: [class, Symbol]",
+    );
+    assert!(before.diff(&before).is_empty());
+    assert_eq!(
+        before.diff(&after),
+        [
+            FieldChange::Text {
+                field: "esid",
+                from: Some("sec-old".to_string()),
+                to: Some("sec-new".to_string()),
+            },
+            FieldChange::Negative {
+                from: before.negative.clone(),
+                to: after.negative.clone(),
+            },
+            FieldChange::FeatureRemoved("BigInt".to_string()),
+            FieldChange::FeatureAdded("Symbol".to_string()),
+        ]
+    );
+    let harness_file = desc_from("defines: [assert, verifyProperty]");
+    assert_eq!(
+        harness_file.diff(&desc_from("defines: [assert, assertEquals]")),
+        [
+            FieldChange::DefineRemoved("verifyProperty".to_string()),
+            FieldChange::DefineAdded("assertEquals".to_string()),
+        ]
+    );
+    assert_eq!(
+        harness_file.diff(&desc_from("description: no defines")),
+        [
+            FieldChange::Text {
+                field: "description",
+                from: None,
+                to: Some("no defines".to_string()),
+            },
+            FieldChange::DefineRemoved("assert".to_string()),
+            FieldChange::DefineRemoved("verifyProperty".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn negative_shorthand() {
+    let negative = desc_from("negative: SyntaxError").negative.unwrap();
+    assert_eq!(negative.phase, Phase::Parse);
+    assert_eq!(negative.kind.as_deref(), Some("SyntaxError"));
+    let negative = desc_from("negative: TypeError").negative.unwrap();
+    assert_eq!(negative.phase, Phase::Runtime);
+    assert_eq!(negative.kind.as_deref(), Some("TypeError"));
+    let negative = desc_from("negative:\n  phase: resolution\n  type: SyntaxError")
+        .negative
+        .unwrap();
+    assert_eq!(negative.phase, Phase::Resolution);
+    assert_eq!(negative.kind.as_deref(), Some("SyntaxError"));
+    assert_eq!(desc_from("negative:").negative, None);
+    assert_eq!(desc_from("description: positive").negative, None);
+}
+
+#[test]
+fn dedupe_descriptions() {
+    let descriptions = [
+        desc_from("description: a\nflags: [module]\nnegative: SyntaxError"),
+        desc_from(
+            "description: a\nflags: [module]\nnegative:\n  phase: parse\n  type: SyntaxError",
+        ),
+        desc_from("description: a\nflags: [module]"),
+        desc_from("description: a\nflags: [onlyStrict]"),
+    ];
+    let unique: std::collections::HashSet<_> = descriptions.iter().collect();
+    assert_eq!(unique.len(), 3);
+}
+
+#[test]
+fn ordered_includes() {
+    let desc = desc_from(
+        "includes: [custom.js, testBigIntTypedArray.js, testTypedArray.js, propertyHelper.js, compareArray.js]",
+    );
+    assert_eq!(
+        desc.ordered_includes(),
+        [
+            "assert.js",
+            "sta.js",
+            "custom.js",
+            "compareArray.js",
+            "propertyHelper.js",
+            "testTypedArray.js",
+            "testBigIntTypedArray.js",
+        ]
+    );
+    // dependencies that aren't declared are not added
+    let desc = desc_from("includes: [other.js, testBigIntTypedArray.js]");
+    assert_eq!(
+        desc.ordered_includes(),
+        ["assert.js", "sta.js", "other.js", "testBigIntTypedArray.js"]
+    );
+}
+
+#[test]
+fn unknown_phase() {
+    let negative = desc_from("negative:\n  phase: link\n  type: SyntaxError")
+        .negative
+        .unwrap();
+    assert_eq!(negative.phase, Phase::Unknown("link".to_string()));
+    assert_eq!(
+        negative.phase.acceptable_actual_phases(),
+        [Phase::Unknown("link".to_string())]
+    );
+    for phase in ["parse", "early", "resolution", "runtime", "link"] {
+        let parsed: Phase = serde_yaml::from_str(phase).unwrap();
+        assert_eq!(parsed.as_str(), phase);
+        assert_eq!(serde_yaml::to_string(&parsed).unwrap().trim(), phase);
+    }
+    assert_eq!(
+        serde_yaml::from_str::<Phase>("early").unwrap(),
+        Phase::Early
+    );
+}
+
+#[test]
+fn label() {
+    let cases = [
+        (
+            "description: >\n  Folded\n  text\ninfo: ignored",
+            "Folded text",
+        ),
+        (
+            "info: |\n\n  First line\n  second line\nesid: sec-a",
+            "First line",
+        ),
+        ("es6id: 1.2\nesid: sec-a", "sec-a"),
+        ("flags: [raw]", ""),
+    ];
+    for (yaml, expected) in cases {
+        assert_eq!(desc_from(yaml).label(), expected, "{}", yaml);
+    }
+}
+
+#[test]
+fn spec_anchor() {
+    let cases = [
+        (
+            "id: a
+es5id: 15.4.4
+es6id: 22.1.3
+esid: sec-a",
+            Some("sec-a"),
+        ),
+        (
+            "id: a
+es5id: 15.4.4
+es6id: 22.1.3",
+            Some("22.1.3"),
+        ),
+        (
+            "id: a
+es5id: 15.4.4",
+            Some("15.4.4"),
+        ),
+        ("id: a", None),
+    ];
+    for (yaml, expected) in cases {
+        assert_eq!(desc_from(yaml).spec_anchor(), expected, "{}", yaml);
+    }
+}
+
+#[test]
+fn ids() {
+    use test262_harness::IdKind;
+    let desc = desc_from("es6id: 22.1.3\nesid: sec-array.prototype.flat\nes5id: 15.4.4");
+    assert_eq!(
+        desc.ids().collect::<Vec<_>>(),
+        [
+            (IdKind::Esid, "sec-array.prototype.flat"),
+            (IdKind::Es5id, "15.4.4"),
+            (IdKind::Es6id, "22.1.3"),
+        ]
+    );
+    assert_eq!(desc_from("flags: [raw]").ids().count(), 0);
+}
+
+#[test]
+fn to_metadata_block() {
+    let desc = desc_from(
+        "description: 'Array.prototype.flat: depth'\nflags: [onlyStrict]\nesid: sec-array.prototype.flat\ninfo: |\n  1. Let O be ? ToObject(this value).\n\n  2. Done.\nfeatures: [Array.prototype.flat]\nincludes: [compareArray.js]\nnegative:\n  phase: parse\n  type: SyntaxError",
+    );
+    let block = desc.to_metadata_block();
+    assert_eq!(
+        block,
+        "/*---\nesid: sec-array.prototype.flat\ndescription: 'Array.prototype.flat: depth'\ninfo: |\n  1. Let O be ? ToObject(this value).\n\n  2. Done.\nnegative:\n  phase: parse\n  type: SyntaxError\nincludes: [compareArray.js]\nflags: [onlyStrict]\nfeatures: [Array.prototype.flat]\n---*/"
+    );
+    let yaml = &block["/*---".len()..block.len() - "---*/".len()];
+    assert_eq!(desc_from(yaml), desc);
+}
+
+#[test]
+fn defines() {
+    let desc = desc_from(
+        "description: |\n    Collection of assertion functions used throughout test262\ndefines:\n  - assert\n  - assertEquals",
+    );
+    assert_eq!(desc.defines(), ["assert", "assertEquals"]);
+    assert!(desc_from("description: a").defines().is_empty());
+}
+
+#[test]
+fn to_metadata_block_round_trips() {
+    let cases = [
+        "description: plain",
+        "description: \"it's 'quoted' # not a comment\"",
+        "description: \"true\"\nid: \"123\"",
+        "info: \"no trailing newline\"",
+        "info: \"  leading space\\n  kept\\n\"",
+        "info: \"trailing blank lines\\n\\n\\n\"",
+        "info: \"\"",
+        "info: \"\\n\"",
+        "info: \"\\n\\n\"",
+        "info: \"windows\\r\\nline endings\\r\\n\"",
+        "info: \"\\n  indented after a blank line\\n\"",
+        "info: \"\\ttab indented\\nthen not\"",
+        "description: \"a \\\"quote\\\" and a \\\\ with\\ttabs\\r\"",
+        "description: |\n  two\n  lines\n",
+        "negative: TypeError\nlocale: [en-US, 'de, DE']",
+        "flags: [raw, CanBlockIsFalse, non-deterministic]",
+        "features: ['[weird]', '{odd}', -dash, '*star']",
+        "defines: [assert, assertEquals]",
+    ];
+    for yaml in cases {
+        let desc = desc_from(yaml);
+        let block = desc.to_metadata_block();
+        let reparsed = desc_from(&block["/*---".len()..block.len() - "---*/".len()]);
+        assert_eq!(reparsed, desc, "{}", block);
+    }
+}
+
+#[test]
+fn multi_line_descriptions() {
+    let cases = [
+        (
+            "description: >\n  Folded across\n  two lines: with a colon\n",
+            "Folded across two lines: with a colon\n",
+        ),
+        ("description: |\n  Literal\n  lines\n", "Literal\nlines\n"),
+        (
+            "description: \"Quoted: with a colon\n  and a continuation\"",
+            "Quoted: with a colon and a continuation",
+        ),
+        (
+            "description: 'single quoted, ends the comment ---*/ early'",
+            "single quoted, ends the comment ---*/ early",
+        ),
+        (
+            "description: plain text spanning\n  a second line",
+            "plain text spanning a second line",
+        ),
+    ];
+    for (meta, expected) in cases {
+        let dir = TestDir::new();
+        dir.write("a.js", format!("{}ok();\n", with_meta(meta)));
+        let test = only_test(&dir);
+        assert_eq!(test.desc.description.as_deref(), Some(expected), "{}", meta);
+        assert_eq!(test.body(), "\nok();\n", "{}", meta);
+    }
+}
+
+#[cfg(feature = "schema")]
+#[test]
+fn validate_schema() {
+    let schema: serde_json::Value = serde_json::from_str(
+        r#"{
+            "type": "object",
+            "required": ["description", "esid"],
+            "additionalProperties": false,
+            "properties": {
+                "esid": { "type": "string", "pattern": "^sec-" },
+                "description": { "type": "string", "minLength": 1 },
+                "info": { "type": "string" },
+                "negative": {
+                    "type": "object",
+                    "required": ["phase", "type"],
+                    "properties": {
+                        "phase": { "enum": ["parse", "resolution", "runtime"] },
+                        "type": { "type": "string" }
+                    }
+                },
+                "features": { "type": "array", "items": { "type": "string" }, "uniqueItems": true },
+                "flags": { "type": "array", "maxItems": 1 }
+            }
+        }"#,
+    )
+    .unwrap();
+    let desc = desc_from(
+        "esid: sec-a\ndescription: a\ninfo: |\n  text\nnegative:\n  phase: parse\n  type: SyntaxError\nfeatures: [a]",
+    );
+    assert_eq!(desc.validate_schema(&schema), Ok(()));
+    let desc = desc_from(
+        "es5id: 1.2\ndescription: a\nnegative:\n  phase: early\n  type: SyntaxError\nflags: [async, module]\nfeatures: [a, a]",
+    );
+    assert_eq!(
+        desc.validate_schema(&schema),
+        Err(vec![
+            "/: \"esid\" is a required property".to_string(),
+            "/features: [\"a\",\"a\"] has non-unique elements".to_string(),
+            "/flags: [\"async\",\"module\"] has more than 1 item".to_string(),
+            "/negative/phase: \"early\" is not one of \"parse\", \"resolution\" or \"runtime\""
+                .to_string(),
+            "/: Additional properties are not allowed ('es5id' was unexpected)".to_string(),
+        ])
+    );
+    let desc = desc_from("esid: a.b\ndescription: a");
+    assert_eq!(
+        desc.validate_schema(&schema),
+        Err(vec!["/esid: \"a.b\" does not match \"^sec-\"".to_string()])
+    );
+    let invalid = serde_json::json!({ "type": 5 });
+    assert!(matches!(
+        desc.validate_schema(&invalid),
+        Err(errors) if errors.len() == 1 && errors[0].starts_with("invalid schema: ")
+    ));
+}
@@ -0,0 +1,130 @@
+use super::support::{TestDir, LICENSE};
+use test262_harness::{Error, Harness};
+
+#[test]
+fn metadata_error_location() {
+    let dir = TestDir::new();
+    dir.write(
+        "bad.js",
+        format!(
+            "{}\n/*---\ndescription: bad\nflags: [notAFlag]\n---*/\n",
+            LICENSE
+        ),
+    );
+    let err = Harness::new(dir.path())
+        .unwrap()
+        .next()
+        .unwrap()
+        .err()
+        .unwrap();
+    match &err {
+        Error::Metadata { line, snippet, .. } => {
+            assert_eq!(*line, 5);
+            assert_eq!(snippet, "flags: [notAFlag]");
+        }
+        e => panic!("expected a metadata error, found {:?}", e),
+    }
+    let message = err.to_string();
+    assert!(message.contains("bad.js:5:"), "{}", message);
+    assert!(message.contains("flags: [notAFlag]"), "{}", message);
+}
+
+#[test]
+fn metadata_error_location_crlf() {
+    let dir = TestDir::new();
+    dir.write(
+        "bad.js",
+        format!(
+            "{}\r\n/*---\r\ndescription: bad\r\nflags: [notAFlag]\r\n---*/\r\n",
+            LICENSE.replace('\n', "\r\n")
+        ),
+    );
+    let err = Harness::new(dir.path())
+        .unwrap()
+        .next()
+        .unwrap()
+        .err()
+        .unwrap();
+    match err {
+        Error::Metadata { line, .. } => assert_eq!(line, 5),
+        e => panic!("expected a metadata error, found {:?}", e),
+    }
+}
+
+#[test]
+fn unknown_error_kind() {
+    let dir = TestDir::new();
+    dir.write(
+        "typo.js",
+        format!(
+            "{}\n/*---\nnegative:\n  phase: parse\n  type: SyntaxErorr\n---*/\n",
+            LICENSE
+        ),
+    );
+    let err = Harness::new(dir.path())
+        .unwrap()
+        .next()
+        .unwrap()
+        .err()
+        .unwrap();
+    match err {
+        Error::UnknownErrorKind { kind, .. } => assert_eq!(kind, "SyntaxErorr"),
+        e => panic!("expected an unknown error kind, found {:?}", e),
+    }
+}
+
+#[test]
+fn metadata_error_field() {
+    let dir = TestDir::new();
+    dir.write(
+        "no-phase.js",
+        format!(
+            "{}\n/*---\ndescription: no phase\nnegative:\n  type: SyntaxError\n---*/\n",
+            LICENSE
+        ),
+    );
+    let err = Harness::new(dir.path())
+        .unwrap()
+        .next()
+        .unwrap()
+        .err()
+        .unwrap();
+    match &err {
+        Error::Metadata {
+            field, metadata, ..
+        } => {
+            assert_eq!(field.as_deref(), Some("negative.phase"));
+            assert_eq!(
+                metadata,
+                "description: no phase\nnegative:\n  type: SyntaxError"
+            );
+        }
+        e => panic!("expected a metadata error, found {:?}", e),
+    }
+    let message = err.to_string();
+    assert!(
+        message.contains("for `negative.phase`: missing field `phase`\n"),
+        "{}",
+        message
+    );
+    assert!(message.contains("    description: no phase"), "{}", message);
+}
+
+#[test]
+fn empty_metadata() {
+    for block in ["/*------*/", "/*---\n\n---*/", "/*---\n  # todo\n---*/"] {
+        let dir = TestDir::new();
+        dir.write("empty.js", format!("{}\n{}\n", LICENSE, block));
+        let err = Harness::new(dir.path())
+            .unwrap()
+            .next()
+            .unwrap()
+            .err()
+            .unwrap();
+        match &err {
+            Error::EmptyMetadata { path } => assert!(path.ends_with("empty.js")),
+            e => panic!("expected empty metadata, found {:?}", e),
+        }
+        assert!(err.to_string().contains("empty.js is empty"), "{}", err);
+    }
+}
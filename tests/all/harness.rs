@@ -0,0 +1,1326 @@
+use super::support::{with_meta, TestDir, LICENSE};
+use std::collections::HashSet;
+use test262_harness::{Flag, Harness, RunMode, SkipMatch, Test, Warning};
+
+fn with_features(features: &str) -> String {
+    with_meta(&format!("description: features\nfeatures: [{}]", features))
+}
+
+#[test]
+fn requiring_feature() {
+    let dir = TestDir::new();
+    dir.write("a.js", with_features("Array.fromAsync"));
+    dir.write("b.js", with_features("Array.fromAsync, BigInt"));
+    dir.write("c.js", with_features("BigInt"));
+    dir.write("d.js", with_features("array.fromasync"));
+    let mut names: Vec<_> = Harness::new(dir.path())
+        .unwrap()
+        .requiring_feature("Array.fromAsync".to_string())
+        .map(|t| t.unwrap().path.file_name().unwrap().to_owned())
+        .collect();
+    names.sort();
+    assert_eq!(names, ["a.js", "b.js"]);
+}
+
+#[test]
+fn peek_path() {
+    let dir = TestDir::new();
+    dir.write("good.js", with_features("BigInt"));
+    dir.write("bad.js", "no metadata here");
+    let mut harness = Harness::new(dir.path()).unwrap();
+    while let Some(path) = harness.peek_path().map(|p| p.to_path_buf()) {
+        assert_eq!(harness.peek_path(), Some(path.as_path()));
+        match harness.next().unwrap() {
+            Ok(test) => assert_eq!(test.path, path),
+            Err(_) => assert!(path.ends_with("bad.js")),
+        }
+    }
+    assert!(harness.next().is_none());
+}
+
+#[test]
+fn new_multi() {
+    let suite = TestDir::new();
+    suite.write("built-ins/a.js", with_features("BigInt"));
+    let local = TestDir::new();
+    local.write("regressions/b.js", with_features("BigInt"));
+    let roots = [suite.path().to_path_buf(), local.path().to_path_buf()];
+    let relative: Vec<_> = Harness::new_multi(&roots)
+        .unwrap()
+        .map(|t| t.unwrap().relative_path().to_path_buf())
+        .collect();
+    assert_eq!(
+        relative,
+        [
+            std::path::Path::new("built-ins/a.js"),
+            std::path::Path::new("regressions/b.js")
+        ]
+    );
+}
+
+#[test]
+fn clone_is_independent() {
+    let dir = TestDir::new();
+    dir.write("a.js", with_features("BigInt"));
+    dir.write("b.js", with_features("BigInt"));
+    let mut harness = Harness::new(dir.path()).unwrap();
+    let snapshot = harness.clone();
+    assert_eq!(harness, snapshot);
+    harness.next().unwrap().unwrap();
+    assert_ne!(harness, snapshot);
+    assert_eq!(snapshot.count(), 2);
+}
+
+#[test]
+fn sorted() {
+    let dir = TestDir::new();
+    for name in ["c.js", "a/b.js", "b.js", "a.js"] {
+        dir.write(name, with_features("BigInt"));
+    }
+    let relative: Vec<_> = Harness::builder(dir.path())
+        .sorted(true)
+        .build()
+        .unwrap()
+        .map(|t| t.unwrap().relative_path().to_str().unwrap().to_string())
+        .collect();
+    assert_eq!(relative, ["a/b.js", "a.js", "b.js", "c.js"]);
+}
+
+#[test]
+fn max_depth() {
+    let dir = TestDir::new();
+    dir.write("a.js", with_features("BigInt"));
+    dir.write("nested/b.js", with_features("BigInt"));
+    dir.write("nested/node_modules/c.js", with_features("BigInt"));
+    let count = |depth| {
+        Harness::builder(dir.path())
+            .max_depth(depth)
+            .build()
+            .unwrap()
+            .count()
+    };
+    assert_eq!(count(1), 1);
+    assert_eq!(count(2), 2);
+    assert_eq!(count(3), 3);
+}
+
+#[test]
+fn collect_includes() {
+    let dir = TestDir::new();
+    dir.write("a.js", with_meta("includes: [compareArray.js]"));
+    dir.write("b.js", with_meta("flags: [async]"));
+    dir.write(
+        "c.js",
+        with_meta("flags: [raw]\nincludes: [fnGlobalObject.js]"),
+    );
+    let includes = Harness::new(dir.path())
+        .unwrap()
+        .collect_includes()
+        .unwrap();
+    assert_eq!(
+        includes.into_iter().collect::<Vec<_>>(),
+        [
+            "assert.js",
+            "compareArray.js",
+            "doneprintHandle.js",
+            "sta.js"
+        ]
+    );
+}
+
+#[test]
+fn flag_histogram() {
+    let dir = TestDir::new();
+    dir.write("a.js", with_meta("flags: [module, async]"));
+    dir.write("b.js", with_meta("flags: [module]"));
+    dir.write("c.js", with_meta("description: no flags"));
+    let histogram = Harness::new(dir.path()).unwrap().flag_histogram().unwrap();
+    assert_eq!(histogram.len(), 2);
+    assert_eq!(histogram[&Flag::Module], 2);
+    assert_eq!(histogram[&Flag::Async], 1);
+}
+
+#[test]
+fn spec_section_coverage() {
+    let dir = TestDir::new();
+    dir.write("a.js", with_meta("esid: sec-array.prototype.flat"));
+    dir.write(
+        "b.js",
+        with_meta("esid: sec-array.prototype.map\nes6id: 22.1.3.16"),
+    );
+    dir.write("c.js", with_meta("es6id: 22.1.3.11"));
+    dir.write("d.js", with_meta("esid: sec-let-and-const-declarations"));
+    dir.write("e.js", with_meta("id: free-form.id.here"));
+    let coverage = Harness::new(dir.path())
+        .unwrap()
+        .spec_section_coverage()
+        .unwrap();
+    let expected: Vec<(String, usize)> = vec![
+        ("22.1".to_string(), 1),
+        ("sec-array.prototype".to_string(), 2),
+        ("sec-let-and-const-declarations".to_string(), 1),
+    ];
+    assert_eq!(coverage.into_iter().collect::<Vec<_>>(), expected);
+}
+
+#[test]
+fn expanded() {
+    let dir = TestDir::new();
+    dir.write("both.js", with_meta("description: both"));
+    dir.write("strict.js", with_meta("flags: [onlyStrict]"));
+    dir.write("module.js", with_meta("flags: [module]"));
+    dir.write("raw.js", with_meta("flags: [raw, noStrict]"));
+    let mut runs: Vec<_> = Harness::new(dir.path())
+        .unwrap()
+        .expanded()
+        .map(|run| {
+            let run = run.unwrap();
+            (
+                run.test
+                    .path
+                    .file_name()
+                    .unwrap()
+                    .to_str()
+                    .unwrap()
+                    .to_string(),
+                run.mode,
+            )
+        })
+        .collect();
+    runs.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(
+        runs,
+        [
+            ("both.js".to_string(), RunMode::NonStrict),
+            ("both.js".to_string(), RunMode::Strict),
+            ("module.js".to_string(), RunMode::Module),
+            ("raw.js".to_string(), RunMode::Raw),
+            ("strict.js".to_string(), RunMode::Strict),
+        ]
+    );
+}
+
+#[test]
+fn transform() {
+    let dir = TestDir::new();
+    let source = format!("{}var x = 1;\n", with_meta("description: transform"));
+    dir.write("a.js", &source);
+    let test = Harness::builder(dir.path())
+        .transform(|body| format!("instrument();{}", body))
+        .build()
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(test.body(), "instrument();\nvar x = 1;\n");
+    assert_eq!(test.source, source);
+    assert_eq!(test.license(), Some(LICENSE));
+}
+
+#[test]
+fn total_size() {
+    let dir = TestDir::new();
+    let a = with_meta("description: a");
+    let b = with_meta("description: bb");
+    dir.write("a.js", &a);
+    dir.write("b.js", &b);
+    dir.write("ignored.txt", "not a test");
+    let harness = Harness::new(dir.path()).unwrap();
+    assert_eq!(harness.total_size().unwrap(), (a.len() + b.len()) as u64);
+}
+
+#[test]
+fn with_skip_list() {
+    let dir = TestDir::new();
+    dir.write("built-ins/a.js", with_meta("esid: sec-a"));
+    dir.write("built-ins/b.js", with_meta("es6id: 1.2.3"));
+    dir.write("language/c.js", with_meta("esid: sec-c"));
+    // parsing this would fail, skipping it by path never reads it
+    dir.write("language/crash.js", "no metadata");
+    let skip: HashSet<String> = ["language/crash.js", "sec-a", "1.2.3"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    let names = |matching| {
+        let mut names: Vec<_> = Harness::new(dir.path())
+            .unwrap()
+            .with_skip_list(skip.clone(), matching)
+            .map(|t| match t {
+                Ok(t) => t.relative_path().to_str().unwrap().to_string(),
+                Err(_) => "error".to_string(),
+            })
+            .collect();
+        names.sort();
+        names
+    };
+    assert_eq!(
+        names(SkipMatch::Path),
+        ["built-ins/a.js", "built-ins/b.js", "language/c.js"]
+    );
+    assert_eq!(names(SkipMatch::Id), ["error", "language/c.js"]);
+    assert_eq!(names(SkipMatch::Either), ["language/c.js"]);
+}
+
+#[test]
+fn with_skip_list_partly_consumed() {
+    let dir = TestDir::new();
+    for name in ["a.js", "b.js", "c.js"] {
+        dir.write(name, with_meta("description: skip"));
+    }
+    let mut harness = Harness::builder(dir.path()).sorted(true).build().unwrap();
+    assert_eq!(harness.next().unwrap().unwrap().stable_id(), "a.js");
+    let skip: HashSet<String> = std::iter::once("a.js".to_string()).collect();
+    let rest: Vec<_> = harness
+        .with_skip_list(skip, SkipMatch::Path)
+        .map(|test| test.unwrap().stable_id())
+        .collect();
+    assert_eq!(rest, ["b.js", "c.js"]);
+}
+
+#[test]
+fn iterate_by_reference() {
+    let dir = TestDir::new();
+    dir.write("a.js", with_meta("description: a"));
+    dir.write("b.js", with_meta("description: b"));
+    let mut harness = Harness::new(dir.path()).unwrap();
+    harness.next().unwrap().unwrap();
+    for _ in 0..2 {
+        let mut count = 0;
+        for test in &harness {
+            test.unwrap();
+            count += 1;
+        }
+        assert_eq!(count, 2);
+    }
+    assert_eq!(harness.count(), 1);
+}
+
+#[test]
+fn using_include() {
+    let dir = TestDir::new();
+    dir.write("a.js", with_meta("includes: [propertyHelper.js]"));
+    dir.write("b.js", with_meta("description: b"));
+    dir.write("c.js", with_meta("flags: [raw]"));
+    let count = |include: &str| {
+        Harness::new(dir.path())
+            .unwrap()
+            .using_include(include.to_string())
+            .count()
+    };
+    assert_eq!(count("propertyHelper.js"), 1);
+    assert_eq!(count("assert.js"), 2);
+    assert_eq!(count("compareArray.js"), 0);
+}
+
+#[cfg(unix)]
+#[test]
+fn follow_links() {
+    let suite = TestDir::new();
+    suite.write("built-ins/a.js", with_meta("description: a"));
+    let dir = TestDir::new();
+    std::os::unix::fs::symlink(suite.path(), dir.path().join("linked")).unwrap();
+    let count = |yes| {
+        Harness::builder(dir.path())
+            .follow_links(yes)
+            .build()
+            .unwrap()
+            .count()
+    };
+    assert_eq!(count(false), 0);
+    assert_eq!(count(true), 1);
+}
+
+#[cfg(unix)]
+#[test]
+fn canonicalize() {
+    let suite = TestDir::new();
+    suite.write("built-ins/a.js", with_meta("description: a"));
+    let dir = TestDir::new();
+    std::os::unix::fs::symlink(suite.path(), dir.path().join("linked")).unwrap();
+    let root = dir.path().join("linked/built-ins/..");
+    let test = Harness::builder(&root)
+        .canonicalize(true)
+        .build()
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    let expected = suite.path().canonicalize().unwrap().join("built-ins/a.js");
+    assert_eq!(test.path, expected);
+    assert_eq!(test.stable_id(), "built-ins/a.js");
+    // off by default
+    let test = Harness::new(&root).unwrap().next().unwrap().unwrap();
+    assert_eq!(test.path, root.join("built-ins/a.js"));
+}
+
+#[test]
+fn collect_skipped() {
+    let dir = TestDir::new();
+    dir.write("a.js", with_meta("description: a"));
+    dir.write("a.js.bak", with_meta("description: a"));
+    dir.write("sub/b.txt", "notes");
+    dir.write("sub/README", "readme");
+    dir.write("sub/c_FIXTURE.js", "export default 1;");
+    let harness = Harness::builder(dir.path())
+        .collect_skipped(true)
+        .build()
+        .unwrap();
+    let mut skipped = harness.skipped().to_vec();
+    skipped.sort();
+    assert_eq!(
+        skipped,
+        [
+            dir.path().join("a.js.bak"),
+            dir.path().join("sub/README"),
+            dir.path().join("sub/b.txt"),
+        ]
+    );
+    assert_eq!(harness.count(), 1);
+    // off by default
+    assert!(Harness::new(dir.path()).unwrap().skipped().is_empty());
+}
+
+#[test]
+fn single() {
+    let dir = TestDir::new();
+    let path = dir.write("a.js", with_meta("description: single"));
+    let test = Harness::single(&path).unwrap();
+    assert_eq!(test.path, path);
+    assert_eq!(test.desc.description.as_deref(), Some("single"));
+    let module = dir.write("b.mjs", with_meta("description: module"));
+    assert!(Harness::single(&module).unwrap().run_as_module());
+    let txt = dir.write("a.txt", with_meta("description: txt"));
+    assert!(matches!(
+        Harness::single(&txt),
+        Err(test262_harness::Error::NotATestFile(_))
+    ));
+    assert!(matches!(
+        Harness::single(dir.path()),
+        Err(test262_harness::Error::NotATestFile(_))
+    ));
+}
+
+#[test]
+fn positives_only() {
+    let dir = TestDir::new();
+    dir.write("pass.js", with_meta("description: pass"));
+    dir.write(
+        "fail.js",
+        with_meta("negative:\n  phase: runtime\n  type: TypeError"),
+    );
+    dir.write("broken.js", "no metadata");
+    let results: Vec<_> = Harness::builder(dir.path())
+        .sorted(true)
+        .build()
+        .unwrap()
+        .positives_only()
+        .collect();
+    assert_eq!(results.len(), 2);
+    assert!(results[0].is_err());
+    assert!(results[1].as_ref().unwrap().path.ends_with("pass.js"));
+}
+
+#[test]
+fn parse_negatives() {
+    let dir = TestDir::new();
+    dir.write(
+        "a.js",
+        with_meta("negative:\n  phase: parse\n  type: SyntaxError"),
+    );
+    dir.write(
+        "b.js",
+        with_meta("negative:\n  phase: runtime\n  type: SyntaxError"),
+    );
+    dir.write(
+        "c.js",
+        with_meta("negative:\n  phase: resolution\n  type: SyntaxError"),
+    );
+    dir.write("d.js", with_meta("negative: SyntaxError"));
+    dir.write("e.js", with_meta("description: pass"));
+    dir.write("f.js", "no metadata");
+    let results: Vec<_> = Harness::builder(dir.path())
+        .sorted(true)
+        .build()
+        .unwrap()
+        .parse_negatives()
+        .map(|test| test.ok().map(|test| test.stable_id()))
+        .collect();
+    assert_eq!(
+        results,
+        [Some("a.js".to_string()), Some("d.js".to_string()), None]
+    );
+}
+
+#[test]
+fn max_features() {
+    let dir = TestDir::new();
+    dir.write("a.js", with_meta("description: none"));
+    dir.write("b.js", with_features("BigInt"));
+    dir.write("c.js", with_features("BigInt, Symbol"));
+    dir.write("d.js", "no metadata");
+    let ids = |n| -> Vec<Option<String>> {
+        Harness::builder(dir.path())
+            .sorted(true)
+            .build()
+            .unwrap()
+            .max_features(n)
+            .map(|test| test.ok().map(|test| test.stable_id()))
+            .collect()
+    };
+    assert_eq!(ids(0), [Some("a.js".to_string()), None]);
+    assert_eq!(
+        ids(1),
+        [Some("a.js".to_string()), Some("b.js".to_string()), None]
+    );
+    assert_eq!(ids(2).len(), 4);
+}
+
+#[test]
+fn scan_flags_matches_full_parse() {
+    let dir = TestDir::new();
+    dir.write(
+        "flow.js",
+        with_meta("flags: [onlyStrict, async] # trailing\nfeatures: [BigInt, 'Symbol.iterator']"),
+    );
+    dir.write("quoted.js", with_meta("features: [\"a, b\", c, 'd, e']"));
+    dir.write(
+        "block.js",
+        with_meta(
+            "description: >\n  flags: [raw]\nflags:\n  - module\nfeatures:\n- Proxy\n- Reflect",
+        ),
+    );
+    dir.write(
+        "multi.js",
+        with_meta(
+            "features: [\n  BigInt,\n  Proxy\n]\nnegative:\n  phase: parse\n  type: SyntaxError",
+        ),
+    );
+    dir.write(
+        "flow-neg.js",
+        with_meta("negative: {phase: resolution, type: SyntaxError}"),
+    );
+    dir.write(
+        "short-neg.js",
+        with_meta("negative: TypeError\nflags: [CanBlockIsFalse]"),
+    );
+    dir.write(
+        "none.js",
+        with_meta("description: nothing to see\ninfo: |\n  features: [Proxy]"),
+    );
+    let harness = Harness::builder(dir.path()).sorted(true).build().unwrap();
+    let scanned: Vec<_> = harness.clone().scan_flags().map(Result::unwrap).collect();
+    let parsed: Vec<_> = harness.map(Result::unwrap).collect();
+    assert_eq!(scanned.len(), 7);
+    for (scan, test) in scanned.iter().zip(&parsed) {
+        assert_eq!(scan.path, test.path);
+        assert_eq!(scan.flags, test.desc.flags, "{:?}", test.path);
+        assert_eq!(scan.features, test.desc.features, "{:?}", test.path);
+        assert_eq!(
+            scan.phase,
+            test.desc.negative.as_ref().map(|neg| neg.phase.clone()),
+            "{:?}",
+            test.path
+        );
+    }
+}
+
+#[test]
+fn scan_flags_filtered() {
+    let dir = TestDir::new();
+    dir.write("a.js", with_meta("esid: sec-a\nfeatures: [BigInt]"));
+    dir.write("b.js", with_meta("esid: sec-b\nfeatures: [BigInt, Proxy]"));
+    dir.write("c.js", with_meta("esid: 'sec-c'\nfeatures: [BigInt]"));
+    dir.write("d.js", with_meta("esid: sec-d"));
+    let config = test262_harness::HarnessConfig {
+        skip: std::iter::once("sec-c".to_string()).collect(),
+        features: vec!["BigInt".to_string()],
+        exclude_features: vec!["Proxy".to_string()],
+        ..Default::default()
+    };
+    let harness = Harness::builder(dir.path())
+        .sorted(true)
+        .config(config)
+        .build()
+        .unwrap();
+    let scanned: Vec<_> = harness
+        .clone()
+        .scan_flags()
+        .map(|scan| scan.unwrap().path)
+        .collect();
+    let parsed: Vec<_> = harness.map(|test| test.unwrap().path).collect();
+    assert_eq!(scanned, [dir.path().join("a.js")]);
+    assert_eq!(scanned, parsed);
+}
+
+#[test]
+fn scan_flags_unknown_flag() {
+    let dir = TestDir::new();
+    dir.write("a.js", with_meta("flags: [bogus]"));
+    let mut scanned = Harness::new(dir.path()).unwrap().scan_flags();
+    assert!(matches!(
+        scanned.next(),
+        Some(Err(test262_harness::Error::UnknownFlag(flag))) if flag == "bogus"
+    ));
+}
+
+#[test]
+fn validate_includes() {
+    let dir = TestDir::new();
+    dir.write("harness/assert.js", "");
+    dir.write("harness/sta.js", "");
+    dir.write("harness/compareArray.js", "");
+    dir.write("test/a.js", with_meta("includes: [compareArray.js]"));
+    dir.write("test/b.js", with_meta("includes: [missing.js]"));
+    let results: Vec<_> = Harness::builder(dir.path().join("test"))
+        .sorted(true)
+        .validate_includes(true)
+        .build()
+        .unwrap()
+        .collect();
+    assert!(results[0].is_ok());
+    match &results[1] {
+        Err(test262_harness::Error::MissingInclude { test, include }) => {
+            assert!(test.ends_with("b.js"));
+            assert_eq!(include, "missing.js");
+        }
+        other => panic!("expected a missing include, found {:?}", other),
+    }
+    // an explicit directory without `sta.js`
+    dir.write("other/assert.js", "");
+    dir.write("other/compareArray.js", "");
+    let mut harness = Harness::builder(dir.path().join("test"))
+        .sorted(true)
+        .harness_dir(dir.path().join("other"))
+        .validate_includes(true)
+        .build()
+        .unwrap();
+    assert!(matches!(
+        harness.next(),
+        Some(Err(test262_harness::Error::MissingInclude { include, .. })) if include == "sta.js"
+    ));
+    // off by default
+    assert!(Harness::new(dir.path().join("test"))
+        .unwrap()
+        .all(|test| test.is_ok()));
+}
+
+#[test]
+fn deny_duplicate_keys() {
+    let dir = TestDir::new();
+    dir.write(
+        "a.js",
+        with_meta("description: a\ninfo: |\n  description: quoted\nfeatures: [a] # features: [b]"),
+    );
+    dir.write("b.js", with_meta("esid: a\nfeatures: [a]\nesid: b"));
+    let results: Vec<_> = Harness::builder(dir.path())
+        .sorted(true)
+        .deny_duplicate_keys(true)
+        .build()
+        .unwrap()
+        .collect();
+    assert!(results[0].is_ok());
+    match &results[1] {
+        Err(test262_harness::Error::DuplicateMetadataKey { path, key }) => {
+            assert!(path.ends_with("b.js"));
+            assert_eq!(key, "esid");
+        }
+        other => panic!("expected a duplicate key, found {:?}", other),
+    }
+    // off by default
+    let mut harness = Harness::builder(dir.path()).sorted(true).build().unwrap();
+    harness.skip_to(1).unwrap();
+    assert!(!matches!(
+        harness.next(),
+        Some(Err(test262_harness::Error::DuplicateMetadataKey { .. }))
+    ));
+}
+
+#[test]
+fn skip_to() {
+    let dir = TestDir::new();
+    for name in ["a.js", "b.js", "c.js"] {
+        dir.write(name, with_meta("description: skip"));
+    }
+    let mut harness = Harness::builder(dir.path()).sorted(true).build().unwrap();
+    assert_eq!(harness.current_index(), 0);
+    harness.skip_to(2).unwrap();
+    assert_eq!(harness.current_index(), 2);
+    assert!(harness.next().unwrap().unwrap().path.ends_with("c.js"));
+    assert_eq!(harness.current_index(), 3);
+    harness.skip_to(1).unwrap();
+    assert!(harness.next().unwrap().unwrap().path.ends_with("b.js"));
+    harness.skip_to(3).unwrap();
+    assert!(harness.next().is_none());
+    assert!(matches!(
+        harness.skip_to(4),
+        Err(test262_harness::Error::IndexOutOfBounds { index: 4, len: 3 })
+    ));
+    assert_eq!(harness.current_index(), 3);
+}
+
+#[test]
+fn statistics() {
+    let dir = TestDir::new();
+    dir.write(
+        "language/a.js",
+        with_meta("flags: [module, async]\nfeatures: [BigInt]"),
+    );
+    dir.write(
+        "language/b.js",
+        with_meta("negative: SyntaxError\nfeatures: [BigInt, Proxy]"),
+    );
+    dir.write("built-ins/c.js", with_meta("flags: [raw]"));
+    dir.write("d.js", with_meta("description: top"));
+    let stats = Harness::new(dir.path()).unwrap().statistics().unwrap();
+    assert_eq!(stats.total, 4);
+    assert_eq!(stats.negative, 1);
+    assert_eq!(stats.asynchronous, 1);
+    assert_eq!(stats.module, 1);
+    assert_eq!(stats.raw, 1);
+    assert_eq!(stats.features.len(), 2);
+    assert_eq!(stats.features["BigInt"], 2);
+    assert_eq!(stats.features["Proxy"], 1);
+    assert_eq!(stats.categories.len(), 2);
+    assert_eq!(stats.categories["language"], 2);
+    assert_eq!(stats.categories["built-ins"], 1);
+}
+
+#[test]
+fn include_staging() {
+    let dir = TestDir::new();
+    dir.write("staging/a.js", with_meta("description: staged"));
+    dir.write("language/staging/b.js", with_meta("description: nested"));
+    dir.write("language/c.js", with_meta("description: stable"));
+    assert_eq!(Harness::new(dir.path()).unwrap().count(), 3);
+    let mut names: Vec<_> = Harness::builder(dir.path())
+        .include_staging(false)
+        .build()
+        .unwrap()
+        .map(|t| t.unwrap().path.file_name().unwrap().to_owned())
+        .collect();
+    names.sort();
+    assert_eq!(names, ["b.js", "c.js"]);
+}
+
+#[test]
+fn minimal_harness_set() {
+    let dir = TestDir::new();
+    let harness_dir = dir.path().join("harness");
+    for name in [
+        "assert.js",
+        "sta.js",
+        "compareArray.js",
+        "propertyHelper.js",
+        "testTypedArray.js",
+        "testBigIntTypedArray.js",
+        "doneprintHandle.js",
+    ] {
+        dir.write(&format!("harness/{}", name), "");
+    }
+    dir.write(
+        "test/a.js",
+        with_meta("includes: [testBigIntTypedArray.js]"),
+    );
+    dir.write("test/b.js", with_meta("flags: [async]"));
+    dir.write("test/c.js", with_meta("flags: [raw]"));
+    let set = Harness::new(dir.path().join("test"))
+        .unwrap()
+        .minimal_harness_set(&harness_dir)
+        .unwrap();
+    let names: Vec<_> = set
+        .iter()
+        .map(|path| path.strip_prefix(&harness_dir).unwrap().to_str().unwrap())
+        .collect();
+    assert_eq!(
+        names,
+        [
+            "assert.js",
+            "doneprintHandle.js",
+            "sta.js",
+            "compareArray.js",
+            "propertyHelper.js",
+            "testTypedArray.js",
+            "testBigIntTypedArray.js",
+        ]
+    );
+    std::fs::remove_file(harness_dir.join("propertyHelper.js")).unwrap();
+    match Harness::new(dir.path().join("test"))
+        .unwrap()
+        .minimal_harness_set(&harness_dir)
+    {
+        Err(test262_harness::Error::MissingInclude { test, include }) => {
+            assert_eq!(include, "propertyHelper.js");
+            assert!(test.ends_with("a.js"));
+        }
+        other => panic!("expected a missing include, found {:?}", other),
+    }
+}
+
+#[test]
+fn validate_all() {
+    use test262_harness::{Error, ProblemKind, ValidationError};
+    let dir = TestDir::new();
+    dir.write("a.js", with_meta("id: shared"));
+    dir.write("b.js", with_meta("id: shared\nflags: [raw, onlyStrict]"));
+    dir.write("c.js", "no metadata");
+    dir.write("d.js", with_meta("flags: [async]"));
+    dir.write("e.js", with_meta("flags: [module]"));
+    let report = Harness::builder(dir.path())
+        .sorted(true)
+        .build()
+        .unwrap()
+        .validate_all();
+    assert_eq!(report.checked, 5);
+    assert!(!report.is_clean());
+    let problems: Vec<_> = report
+        .problems
+        .iter()
+        .map(|problem| {
+            (
+                problem.path.file_name().unwrap().to_str().unwrap(),
+                &problem.kind,
+            )
+        })
+        .collect();
+    assert_eq!(problems.len(), 4);
+    assert!(matches!(
+        problems[0],
+        (
+            "b.js",
+            ProblemKind::Invalid(ValidationError::RawConflict(Flag::OnlyStrict))
+        )
+    ));
+    assert!(matches!(
+        problems[1],
+        ("b.js", ProblemKind::DuplicateId { id, first }) if id == "shared" && first.ends_with("a.js")
+    ));
+    assert!(matches!(
+        problems[2],
+        ("c.js", ProblemKind::Parse(Error::DescriptionInvalid(_)))
+    ));
+    assert!(matches!(
+        problems[3],
+        (
+            "d.js",
+            ProblemKind::Invalid(ValidationError::AsyncWithoutDone)
+        )
+    ));
+}
+
+#[test]
+fn with_config() {
+    use test262_harness::HarnessConfig;
+    let dir = TestDir::new();
+    dir.write("language/a.js", with_features("BigInt"));
+    dir.write("language/b.js", with_features("BigInt, Proxy"));
+    dir.write(
+        "language/c.js",
+        with_meta("id: skip-me\nfeatures: [BigInt]"),
+    );
+    dir.write("language/d.js", with_features("BigInt"));
+    dir.write("language/e.mjs", with_features("BigInt"));
+    dir.write("language/f_FIXTURE.js", "export var f;");
+    dir.write("staging/g.js", with_features("BigInt"));
+    dir.write("annexB/h.js", with_features("BigInt"));
+    dir.write("language/i.js", with_features("Symbol"));
+    let config: HarnessConfig = serde_yaml::from_str(
+        "extensions: [js, mjs]\ninclude_staging: false\ninclude_annex_b: false\nskip: [language/d.js, skip-me]\nfeatures: [BigInt]\nexclude_features: [Proxy]",
+    )
+    .unwrap();
+    assert!(!config.include_fixtures);
+    let mut names: Vec<_> = Harness::with_config(dir.path(), config)
+        .unwrap()
+        .map(|t| t.unwrap().path.file_name().unwrap().to_owned())
+        .collect();
+    names.sort();
+    assert_eq!(names, ["a.js", "e.mjs"]);
+
+    let config: HarnessConfig = serde_yaml::from_str("include_fixtures: true").unwrap();
+    assert_eq!(config.extensions, ["js"]);
+    assert!(config.include_staging && config.include_annex_b);
+    let fixture = Harness::with_config(dir.path(), config)
+        .unwrap()
+        .find_map(|t| t.err())
+        .unwrap();
+    assert!(matches!(
+        fixture,
+        test262_harness::Error::DescriptionInvalid(path) if path.ends_with("f_FIXTURE.js")
+    ));
+}
+
+#[test]
+fn size_hint() {
+    let dir = TestDir::new();
+    for name in ["a.js", "b.js", "c.js"] {
+        dir.write(name, with_meta("description: hint"));
+    }
+    let mut harness = Harness::new(dir.path()).unwrap();
+    assert_eq!(harness.iter().size_hint(), (3, Some(3)));
+    for remaining in (0..3).rev() {
+        harness.next();
+        assert_eq!(harness.size_hint(), (remaining, Some(remaining)));
+    }
+    harness.next();
+    assert_eq!(harness.size_hint(), (0, Some(0)));
+    let config = test262_harness::HarnessConfig {
+        features: vec!["BigInt".to_string()],
+        ..Default::default()
+    };
+    let harness = Harness::with_config(dir.path(), config).unwrap();
+    assert_eq!(harness.size_hint(), (0, Some(3)));
+}
+
+#[test]
+fn global_includes() {
+    let dir = TestDir::new();
+    dir.write(
+        "a.js",
+        with_meta("flags: [async]\nincludes: [shim.js, compareArray.js]"),
+    );
+    dir.write("b.js", with_meta("flags: [raw]"));
+    let mut harness = Harness::builder(dir.path())
+        .sorted(true)
+        .global_includes(vec!["shim.js".to_string(), "polyfill.js".to_string()])
+        .build()
+        .unwrap();
+    let test = harness.next().unwrap().unwrap();
+    assert_eq!(
+        test.effective_includes(),
+        [
+            "shim.js",
+            "polyfill.js",
+            "assert.js",
+            "sta.js",
+            "doneprintHandle.js",
+            "compareArray.js",
+        ]
+    );
+    assert_eq!(
+        test.desc.effective_includes(),
+        [
+            "assert.js",
+            "sta.js",
+            "doneprintHandle.js",
+            "shim.js",
+            "compareArray.js"
+        ]
+    );
+    let raw = harness.next().unwrap().unwrap();
+    assert!(raw.effective_includes().is_empty());
+}
+
+#[test]
+fn requiring_feature_case_insensitive() {
+    use test262_harness::{FeatureMatch, HarnessConfig};
+    let dir = TestDir::new();
+    dir.write("a.js", with_features("Symbol.asyncIterator"));
+    dir.write("b.js", with_features("symbol.asynciterator"));
+    dir.write("c.js", with_features("BigInt"));
+    let matching = |matching| {
+        let mut names: Vec<_> = Harness::new(dir.path())
+            .unwrap()
+            .requiring_feature_matching("Symbol.asyncIterator".to_string(), matching)
+            .map(|t| t.unwrap().path.file_name().unwrap().to_owned())
+            .collect();
+        names.sort();
+        names
+    };
+    assert_eq!(matching(FeatureMatch::Exact), ["a.js"]);
+    assert_eq!(matching(FeatureMatch::CaseInsensitive), ["a.js", "b.js"]);
+
+    let config: HarnessConfig =
+        serde_yaml::from_str("exclude_features: [SYMBOL.ASYNCITERATOR]").unwrap();
+    assert_eq!(config.feature_match, FeatureMatch::Exact);
+    assert_eq!(Harness::with_config(dir.path(), config).unwrap().count(), 3);
+    let config: HarnessConfig = serde_yaml::from_str(
+        "exclude_features: [SYMBOL.ASYNCITERATOR]\nfeature_match: case_insensitive",
+    )
+    .unwrap();
+    let names: Vec<_> = Harness::with_config(dir.path(), config)
+        .unwrap()
+        .map(|t| t.unwrap().path.file_name().unwrap().to_owned())
+        .collect();
+    assert_eq!(names, ["c.js"]);
+}
+
+#[test]
+fn with_cache() {
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+        time::{Duration, SystemTime},
+    };
+    let dir = TestDir::new();
+    let path = dir.write("a.js", with_meta("description: original"));
+    let cache = Arc::new(Mutex::new(HashMap::new()));
+    let description = |cache: &Arc<Mutex<_>>| {
+        Harness::new(dir.path())
+            .unwrap()
+            .with_cache(Arc::clone(cache))
+            .next()
+            .unwrap()
+            .unwrap()
+            .desc
+            .description
+            .unwrap()
+    };
+    assert_eq!(description(&cache), "original");
+    assert_eq!(cache.lock().unwrap().len(), 1);
+
+    // an unchanged file is never read again,
+    // so its cached entry is what comes back
+    cache
+        .lock()
+        .unwrap()
+        .get_mut(&path)
+        .unwrap()
+        .1
+        .desc
+        .description = Some("cached".to_string());
+    assert_eq!(description(&cache), "cached");
+
+    std::fs::write(&path, with_meta("description: changed")).unwrap();
+    std::fs::File::options()
+        .write(true)
+        .open(&path)
+        .unwrap()
+        .set_modified(SystemTime::now() + Duration::from_secs(60))
+        .unwrap();
+    assert_eq!(description(&cache), "changed");
+    assert_eq!(
+        cache.lock().unwrap()[&path].1.desc.description.as_deref(),
+        Some("changed")
+    );
+}
+
+#[test]
+fn expecting_error() {
+    let dir = TestDir::new();
+    dir.write(
+        "a.js",
+        with_meta("negative:\n  phase: runtime\n  type: TypeError"),
+    );
+    dir.write("b.js", with_meta("negative: RangeError"));
+    dir.write(
+        "c.js",
+        with_meta("negative:\n  phase: runtime\n  type: Test262Error"),
+    );
+    dir.write(
+        "d.js",
+        with_meta("negative:\n  phase: runtime\n  type: Test262:AsyncTestFailure"),
+    );
+    dir.write("e.js", with_meta("description: positive"));
+    let names = |tests: Vec<Result<test262_harness::Test, test262_harness::Error>>| {
+        let mut names: Vec<_> = tests
+            .into_iter()
+            .map(|t| t.unwrap().path.file_name().unwrap().to_owned())
+            .collect();
+        names.sort();
+        names
+    };
+    let harness = || Harness::new(dir.path()).unwrap();
+    assert_eq!(
+        names(harness().expecting_error("TypeError").collect()),
+        ["a.js"]
+    );
+    assert_eq!(
+        names(harness().expecting_error("Test262Error").collect()),
+        ["c.js"]
+    );
+    assert_eq!(
+        names(harness().expecting_test262_error().collect()),
+        ["c.js", "d.js"]
+    );
+}
+
+#[test]
+fn async_markers() {
+    let dir = TestDir::new();
+    dir.write("a.js", format!("{}$DONE();", with_meta("flags: [async]")));
+    let test = Harness::new(dir.path()).unwrap().next().unwrap().unwrap();
+    assert_eq!(test.async_pass_marker(), "Test262:AsyncTestComplete");
+    assert_eq!(test.async_fail_prefix(), "Test262:AsyncTestFailure:");
+    let test = Harness::builder(dir.path())
+        .async_markers("Done!".to_string(), "Failed: ".to_string())
+        .build()
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(test.async_pass_marker(), "Done!");
+    assert_eq!(test.async_fail_prefix(), "Failed: ");
+}
+
+#[test]
+fn chunks() {
+    let dir = TestDir::new();
+    for name in ["a.js", "b.js", "c.js", "d.js", "e.js"] {
+        dir.write(name, with_meta("description: chunk"));
+    }
+    let harness = Harness::builder(dir.path()).sorted(true).build().unwrap();
+    let chunks: Vec<Vec<String>> = harness
+        .chunks(2)
+        .map(|chunk| {
+            chunk
+                .into_iter()
+                .map(|test| test.unwrap().stable_id())
+                .collect()
+        })
+        .collect();
+    assert_eq!(
+        chunks,
+        [vec!["a.js", "b.js"], vec!["c.js", "d.js"], vec!["e.js"]]
+    );
+    let harness = Harness::new(dir.path()).unwrap();
+    assert_eq!(harness.chunks(5).count(), 1);
+}
+
+#[test]
+fn sample() {
+    let dir = TestDir::new();
+    for idx in 0..200 {
+        dir.write(
+            &format!("dir{}/t{}.js", idx % 4, idx),
+            with_meta("description: sample"),
+        );
+    }
+    let ids = |fraction, seed| -> Vec<String> {
+        Harness::builder(dir.path())
+            .sorted(true)
+            .build()
+            .unwrap()
+            .sample(fraction, seed)
+            .map(|test| test.unwrap().stable_id())
+            .collect()
+    };
+    let picked = ids(0.25, 7);
+    assert!((25..=75).contains(&picked.len()), "{}", picked.len());
+    assert_eq!(picked, ids(0.25, 7));
+    assert_ne!(picked, ids(0.25, 8));
+    for dir in ["dir0/", "dir1/", "dir2/", "dir3/"] {
+        assert!(picked.iter().any(|id| id.starts_with(dir)), "{}", dir);
+    }
+    assert!(ids(0.0, 7).is_empty());
+    assert_eq!(ids(1.0, 7).len(), 200);
+}
+
+#[test]
+fn by_generator_template() {
+    let dir = TestDir::new();
+    let generated = |case: &str, template: &str| {
+        format!(
+            "// This file was procedurally generated from the following sources:\n// - src/{}.case\n// - src/{}.template\n{}",
+            case,
+            template,
+            with_meta("flags: [generated]")
+        )
+    };
+    dir.write("gen/a.js", generated("dstr/a", "dstr/default/cls-decl"));
+    dir.write("gen/b.js", generated("dstr/b", "dstr/default/cls-decl"));
+    dir.write("gen/c.js", generated("dstr/a", "dstr/default/fn-expr"));
+    dir.write("other/d.js", with_meta("flags: [generated]"));
+    dir.write("other/e.js", with_meta("description: handwritten"));
+    let groups = Harness::builder(dir.path())
+        .sorted(true)
+        .build()
+        .unwrap()
+        .by_generator_template()
+        .unwrap();
+    let ids = |key: &str| -> Vec<String> { groups[key].iter().map(Test::stable_id).collect() };
+    assert_eq!(groups.len(), 4);
+    assert_eq!(
+        ids("src/dstr/default/cls-decl.template"),
+        ["gen/a.js", "gen/b.js"]
+    );
+    assert_eq!(ids("src/dstr/default/fn-expr.template"), ["gen/c.js"]);
+    assert_eq!(ids("other"), ["other/d.js"]);
+    assert_eq!(ids("__handwritten__"), ["other/e.js"]);
+}
+
+#[test]
+fn with_resolved_includes() {
+    let dir = TestDir::new();
+    for name in [
+        "assert.js",
+        "sta.js",
+        "doneprintHandle.js",
+        "compareArray.js",
+    ] {
+        dir.write(&format!("harness/{}", name), "");
+    }
+    dir.write(
+        "test/a.js",
+        format!(
+            "{}$DONE();",
+            with_meta("flags: [async]\nincludes: [compareArray.js]")
+        ),
+    );
+    dir.write("test/b.js", with_meta("includes: [missing.js]"));
+    dir.write("test/c.js", with_meta("flags: [raw]"));
+    let harness_dir = dir.path().join("harness");
+    let results: Vec<_> = Harness::builder(dir.path().join("test"))
+        .sorted(true)
+        .build()
+        .unwrap()
+        .with_resolved_includes(&harness_dir)
+        .collect();
+    let resolved = results[0].as_ref().unwrap();
+    assert!(resolved.test.path.ends_with("a.js"));
+    assert_eq!(
+        resolved.includes,
+        [
+            "assert.js",
+            "sta.js",
+            "doneprintHandle.js",
+            "compareArray.js"
+        ]
+        .iter()
+        .map(|name| harness_dir.join(name))
+        .collect::<Vec<_>>()
+    );
+    assert!(matches!(
+        &results[1],
+        Err(test262_harness::Error::MissingInclude { test, include })
+            if test.ends_with("b.js") && include == "missing.js"
+    ));
+    assert!(results[2].as_ref().unwrap().includes.is_empty());
+}
+
+#[test]
+fn override_include() {
+    let dir = TestDir::new();
+    for name in ["assert.js", "sta.js"] {
+        dir.write(&format!("harness/{}", name), "");
+    }
+    let patched = dir.write("engine/doneprintHandle.js", "");
+    dir.write(
+        "test/a.js",
+        format!("{}$DONE();", with_meta("flags: [async]")),
+    );
+    let harness_dir = dir.path().join("harness");
+    let harness = Harness::builder(dir.path().join("test"))
+        .override_include("doneprintHandle.js", &patched)
+        .validate_includes(true)
+        .build()
+        .unwrap();
+    let resolved: Vec<_> = harness
+        .clone()
+        .with_resolved_includes(&harness_dir)
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        resolved[0].includes,
+        [
+            harness_dir.join("assert.js"),
+            harness_dir.join("sta.js"),
+            patched.clone()
+        ]
+    );
+    assert_eq!(
+        harness.minimal_harness_set(&harness_dir).unwrap(),
+        [
+            harness_dir.join("assert.js"),
+            patched,
+            harness_dir.join("sta.js")
+        ]
+    );
+    // without the override the harness directory is missing it
+    let mut harness = Harness::builder(dir.path().join("test"))
+        .validate_includes(true)
+        .build()
+        .unwrap();
+    assert!(matches!(
+        harness.next(),
+        Some(Err(test262_harness::Error::MissingInclude { include, .. }))
+            if include == "doneprintHandle.js"
+    ));
+}
+
+#[test]
+fn metadata_delimiters() {
+    let dir = TestDir::new();
+    dir.write(
+        "a.js",
+        format!(
+            "{}\n/*===\ndescription: a\nflags: [onlyStrict]\n===*/\nbody();\n",
+            LICENSE
+        ),
+    );
+    let harness = Harness::builder(dir.path())
+        .metadata_delimiters("/*===".to_string(), "===*/".to_string())
+        .build()
+        .unwrap();
+    let test = harness.clone().next().unwrap().unwrap();
+    assert_eq!(test.desc.description.as_deref(), Some("a"));
+    assert_eq!(test.body(), "\nbody();\n");
+    assert_eq!(test.license(), Some(LICENSE));
+    assert_eq!(
+        &test.source[test.metadata_range()],
+        "\ndescription: a\nflags: [onlyStrict]\n"
+    );
+    let scan = harness.scan_flags().next().unwrap().unwrap();
+    assert_eq!(scan.flags, [Flag::OnlyStrict]);
+    assert!(matches!(
+        Harness::new(dir.path()).unwrap().next(),
+        Some(Err(test262_harness::Error::DescriptionInvalid(_)))
+    ));
+}
+
+#[test]
+fn lenient() {
+    let dir = TestDir::new();
+    dir.write("harness/assert.js", "");
+    dir.write("harness/sta.js", "");
+    dir.write(
+        "test/a.js",
+        "/*---\ndescription: messy\nflags: [onlyStrict, notAFlag]\nlocale: [en-US, 'not a locale']\nincludes: {a: b}\nnegative:\n  phase: runtime\n  type: Wat\nfeatures: [BigInt]\n---*/\n",
+    );
+    dir.write("test/b.js", format!("{}\n/*---\n---*/\n", LICENSE));
+    dir.write(
+        "test/c.js",
+        format!("{}\n/*---\ndescription: [unclosed\n---*/\n", LICENSE),
+    );
+    dir.write("test/d.js", "no metadata");
+    dir.write("test/e.js", with_meta("includes: [missing.js]"));
+    let results: Vec<_> = Harness::builder(dir.path().join("test"))
+        .sorted(true)
+        .validate_includes(true)
+        .build()
+        .unwrap()
+        .lenient()
+        .collect();
+    assert_eq!(results.len(), 5);
+    let messy = results[0].as_ref().unwrap();
+    let desc = &messy.test.desc;
+    assert_eq!(desc.description.as_deref(), Some("messy"));
+    assert_eq!(desc.flags, [Flag::OnlyStrict]);
+    assert_eq!(desc.features, ["BigInt"]);
+    assert!(desc.includes.is_empty());
+    assert_eq!(
+        desc.negative.as_ref().and_then(|neg| neg.kind.as_deref()),
+        Some("Wat")
+    );
+    let warnings = &messy.warnings;
+    assert_eq!(warnings.len(), 5, "{:?}", warnings);
+    assert_eq!(warnings[0], Warning::UnknownFlag("notAFlag".to_string()));
+    assert!(matches!(&warnings[1], Warning::InvalidField { field, .. } if field == "includes"));
+    assert_eq!(
+        warnings[2],
+        Warning::MalformedLocale("not a locale".to_string())
+    );
+    assert_eq!(warnings[3], Warning::UnknownErrorKind("Wat".to_string()));
+    assert_eq!(warnings[4], Warning::MissingLicense);
+    assert_eq!(
+        results[1].as_ref().unwrap().warnings,
+        [Warning::EmptyMetadata]
+    );
+    assert!(matches!(
+        results[2].as_ref().unwrap().warnings[..],
+        [Warning::InvalidYaml(_)]
+    ));
+    assert!(results[3].is_err());
+    assert_eq!(
+        results[4].as_ref().unwrap().warnings,
+        [Warning::MissingInclude("missing.js".to_string())]
+    );
+}
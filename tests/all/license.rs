@@ -0,0 +1,190 @@
+use super::support::{only_test, TestDir, LICENSE};
+use test262_harness::{Harness, HarnessConfig};
+
+#[test]
+fn license_lf() {
+    let dir = TestDir::new();
+    dir.write(
+        "lf.js",
+        format!("{}\n/*---\ndescription: lf\n---*/\n", LICENSE),
+    );
+    let test = only_test(&dir);
+    assert_eq!(test.license(), Some(LICENSE));
+}
+
+#[test]
+fn license_crlf() {
+    let dir = TestDir::new();
+    let license = LICENSE.replace('\n', "\r\n");
+    dir.write(
+        "crlf.js",
+        format!("{}\r\n/*---\r\ndescription: crlf\r\n---*/\r\n", license),
+    );
+    let test = only_test(&dir);
+    assert_eq!(test.license(), Some(license.as_str()));
+    assert!(test.source.contains("\r\n"));
+}
+
+#[test]
+fn license_mixed_line_endings() {
+    let dir = TestDir::new();
+    let license = LICENSE.replacen('\n', "\r\n", 1);
+    dir.write(
+        "mixed.js",
+        format!("{}\n/*---\ndescription: mixed\n---*/\n", license),
+    );
+    let test = only_test(&dir);
+    assert_eq!(test.license(), Some(license.as_str()));
+}
+
+#[test]
+fn license_missing() {
+    let dir = TestDir::new();
+    dir.write("none.js", "/*---\ndescription: none\n---*/\n");
+    let test = only_test(&dir);
+    assert_eq!(test.license(), None);
+}
+
+#[test]
+fn without_license() {
+    let dir = TestDir::new();
+    dir.write(
+        "a.js",
+        format!(
+            "// leading\n{}\n/*---\ndescription: a\n---*/\nbody;\n",
+            LICENSE
+        ),
+    );
+    let test = only_test(&dir);
+    assert_eq!(
+        test.without_license(),
+        "// leading\n\n/*---\ndescription: a\n---*/\nbody;\n"
+    );
+    let dir = TestDir::new();
+    dir.write("none.js", "/*---\ndescription: none\n---*/\n");
+    let test = only_test(&dir);
+    assert_eq!(test.without_license(), test.source);
+}
+
+#[test]
+fn leading_comment() {
+    let cases = [
+        (
+            format!("#!/usr/bin/env node\n{}\n/*---\ndescription: a\n---*/\n", LICENSE),
+            Some("#!/usr/bin/env node"),
+        ),
+        (
+            format!(
+                "// This file was procedurally generated.\n/* from a template */\n\n{}\n/*---\ndescription: a\n---*/\n",
+                LICENSE
+            ),
+            Some("// This file was procedurally generated.\n/* from a template */"),
+        ),
+        (
+            "// Some other notice\r\n/*---\r\ndescription: a\r\n---*/\r\n".to_string(),
+            Some("// Some other notice"),
+        ),
+        (format!("{}\n/*---\ndescription: a\n---*/\n", LICENSE), None),
+        (
+            "'use strict';\n// not leading\n/*---\ndescription: a\n---*/\n".to_string(),
+            None,
+        ),
+    ];
+    for (source, comment) in cases {
+        let dir = TestDir::new();
+        dir.write("a.js", &source);
+        let test = only_test(&dir);
+        assert_eq!(test.leading_comment(), comment, "{}", source);
+    }
+}
+
+#[test]
+fn tests_without_license() {
+    let dir = TestDir::new();
+    dir.write(
+        "a.js",
+        format!("{}\n/*---\ndescription: a\n---*/\n", LICENSE),
+    );
+    dir.write("b.js", "/*---\ndescription: b\n---*/\n");
+    dir.write(
+        "c.js",
+        "// Copyright nobody\n/*---\ndescription: c\n---*/\n",
+    );
+    dir.write("d_FIXTURE.js", "export default 1;\n");
+    let config = HarnessConfig {
+        include_fixtures: true,
+        ..HarnessConfig::default()
+    };
+    let mut harness = Harness::builder(dir.path())
+        .sorted(true)
+        .config(config)
+        .build()
+        .unwrap();
+    let paths = harness.clone().tests_without_license().unwrap();
+    assert_eq!(paths, [dir.path().join("b.js"), dir.path().join("c.js")]);
+    harness.skip_to(2).unwrap();
+    assert_eq!(
+        harness.tests_without_license().unwrap(),
+        [dir.path().join("c.js")]
+    );
+}
+
+#[test]
+fn licenses() {
+    let dir = TestDir::new();
+    let generator = "// Copyright (C) 2020 the generator authors. All rights reserved.\r\n// See LICENSE for details.";
+    dir.write(
+        "a.js",
+        format!(
+            "{}\n{}\r\n/*---\ndescription: a\n---*/\n",
+            LICENSE, generator
+        ),
+    );
+    let test = only_test(&dir);
+    assert_eq!(test.license(), Some(LICENSE));
+    assert_eq!(test.licenses(), [LICENSE, generator]);
+    dir.write("a.js", "/*---\ndescription: a\n---*/\n");
+    assert!(only_test(&dir).licenses().is_empty());
+}
+
+#[test]
+fn stacked_copyright_lines() {
+    let license = "// Copyright (C) 2017 Mozilla Corporation. All rights reserved.\n// Copyright (C) 2019 Igalia, S.L. All rights reserved.\n// This code is governed by the BSD license found in the LICENSE file.";
+    let dir = TestDir::new();
+    dir.write(
+        "a.js",
+        format!("{}\n/*---\ndescription: a\n---*/\n", license),
+    );
+    let test = only_test(&dir);
+    assert_eq!(test.license(), Some(license));
+    assert_eq!(test.licenses(), [license]);
+    assert_eq!(test.license_year(), Some("2017"));
+    // a separate header after the stacked
+    // lines is its own entry
+    let generator = "// Copyright (C) 2020 the generator authors. All rights reserved.\n// See LICENSE for details.";
+    dir.write(
+        "a.js",
+        format!("{}\n{}\n/*---\ndescription: a\n---*/\n", license, generator),
+    );
+    assert_eq!(only_test(&dir).licenses(), [license, generator]);
+}
+
+#[test]
+fn license_years() {
+    let dir = TestDir::new();
+    let license = |year: &str| {
+        format!(
+            "// Copyright (C) {} the authors. All rights reserved.\r\n// This code is governed by the BSD license found in the LICENSE file.\r\n/*---\r\ndescription: a\r\n---*/\r\n",
+            year
+        )
+    };
+    dir.write("a.js", license("2017"));
+    dir.write("b.js", license("2017"));
+    dir.write("c.js", license("2020"));
+    dir.write("d.js", "/*---\ndescription: none\n---*/\n");
+    let test = Harness::single(dir.path().join("c.js")).unwrap();
+    assert_eq!(test.license_year(), Some("2020"));
+    let years = Harness::new(dir.path()).unwrap().license_years().unwrap();
+    let expected: Vec<(String, usize)> = vec![("2017".to_string(), 2), ("2020".to_string(), 1)];
+    assert_eq!(years.into_iter().collect::<Vec<_>>(), expected);
+}
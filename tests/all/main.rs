@@ -1,3 +1,12 @@
+mod description;
+mod error;
+mod harness;
+mod license;
+#[cfg(feature = "junit")]
+mod report;
+mod support;
+mod test;
+
 use test262_harness::{Flag, Harness, Phase};
 
 #[test]
@@ -7,6 +16,18 @@ fn check_all_from_repo() {
     }
 }
 
+#[test]
+fn scan_flags_from_repo() {
+    let harness = Harness::new("test262/test").unwrap();
+    let scanned = harness.clone().scan_flags();
+    for (scan, test) in scanned.zip(harness) {
+        let (scan, test) = (scan.unwrap(), test.unwrap());
+        assert_eq!(scan.flags, test.desc.flags, "{:?}", test.path);
+        assert_eq!(scan.features, test.desc.features, "{:?}", test.path);
+        assert_eq!(scan.phase, test.desc.negative.map(|neg| neg.phase));
+    }
+}
+
 #[test]
 fn check_readme_code() {
     for test in Harness::new("test262/test").unwrap() {
@@ -34,6 +55,7 @@ fn check_readme_code() {
                 Phase::Early => println!("after parsing but before evaluation"),
                 Phase::Resolution => println!("while resolving es6 modules"),
                 Phase::Runtime => println!("during evaluation"),
+                Phase::Unknown(phase) => println!("during the {} phase", phase),
             }
         }
         if let Some(info) = &test.desc.info {
@@ -0,0 +1,63 @@
+use super::support::{with_meta, TestDir};
+use test262_harness::{
+    report::{JUnitBuilder, Outcome},
+    Harness,
+};
+
+#[test]
+fn junit() {
+    let dir = TestDir::new();
+    dir.write(
+        "built-ins/a.js",
+        with_meta("esid: sec-a\ndescription: a < b & \"c\""),
+    );
+    dir.write("language/b.js", with_meta("description: b"));
+    dir.write("language/c.js", with_meta("description: c"));
+    dir.write("language/d.js", with_meta("description: d"));
+    let tests: Vec<_> = Harness::builder(dir.path())
+        .sorted(true)
+        .build()
+        .unwrap()
+        .map(Result::unwrap)
+        .collect();
+    let outcomes = vec![
+        Outcome::Pass,
+        Outcome::Fail("expected 1\nbut got 2".to_string()),
+        Outcome::Error("crashed\u{0}".to_string()),
+        Outcome::Skip(Some("no <Intl>".to_string())),
+    ];
+    let xml = JUnitBuilder::new("test262")
+        .cases(tests.into_iter().zip(outcomes))
+        .build();
+    assert_eq!(
+        xml,
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuite name="test262" tests="4" failures="1" errors="1" skipped="1">
+  <testcase name="sec-a" classname="built-ins">
+    <properties>
+      <property name="description" value="a &lt; b &amp; &quot;c&quot;"/>
+    </properties>
+  </testcase>
+  <testcase name="language/b.js" classname="language">
+    <properties>
+      <property name="description" value="b"/>
+    </properties>
+    <failure message="expected 1">expected 1
+but got 2</failure>
+  </testcase>
+  <testcase name="language/c.js" classname="language">
+    <properties>
+      <property name="description" value="c"/>
+    </properties>
+    <error message="crashed">crashed</error>
+  </testcase>
+  <testcase name="language/d.js" classname="language">
+    <properties>
+      <property name="description" value="d"/>
+    </properties>
+    <skipped message="no &lt;Intl&gt;"/>
+  </testcase>
+</testsuite>
+"#
+    );
+}
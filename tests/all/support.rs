@@ -0,0 +1,61 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use test262_harness::{Harness, Test};
+
+pub const LICENSE: &str = "// Copyright (C) 2020 the contributors. All rights reserved.\n// This code is governed by the BSD license found in the LICENSE file.";
+
+/// A test source with the standard
+/// license and `meta` as the metadata
+pub fn with_meta(meta: &str) -> String {
+    format!("{}\n/*---\n{}\n---*/\n", LICENSE, meta)
+}
+
+/// A scratch test directory that is
+/// removed when dropped
+pub struct TestDir {
+    pub root: PathBuf,
+}
+
+impl TestDir {
+    pub fn new() -> Self {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let root = std::env::temp_dir().join(format!(
+            "test262-harness-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::SeqCst)
+        ));
+        std::fs::create_dir_all(&root).unwrap();
+        Self { root }
+    }
+
+    /// Write `contents` to `name` relative to
+    /// the root, creating any parent directories
+    pub fn write(&self, name: &str, contents: impl AsRef<[u8]>) -> PathBuf {
+        let path = self.root.join(name);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).unwrap();
+        }
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.root
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.root);
+    }
+}
+
+/// Parse the single test in `dir`
+pub fn only_test(dir: &TestDir) -> Test {
+    let mut harness = Harness::new(dir.path()).unwrap();
+    let test = harness.next().unwrap().unwrap();
+    assert!(harness.next().is_none());
+    test
+}
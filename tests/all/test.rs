@@ -0,0 +1,721 @@
+use super::support::{only_test, with_meta, TestDir, LICENSE};
+use test262_harness::{Expectation, Flag, Invocation, Phase, ValidationError};
+
+#[test]
+fn expectation() {
+    let dir = TestDir::new();
+    dir.write("pass.js", with_meta("description: pass\nflags: [raw]"));
+    let test = only_test(&dir);
+    assert!(!test.expects_throw());
+    assert_eq!(test.expectation(), Expectation::Pass);
+
+    let dir = TestDir::new();
+    dir.write(
+        "throw.js",
+        with_meta("description: throw\nnegative:\n  phase: parse\n  type: SyntaxError"),
+    );
+    let test = only_test(&dir);
+    assert!(test.expects_throw());
+    assert_eq!(
+        test.expectation(),
+        Expectation::Throw {
+            phase: Phase::Parse,
+            kind: Some("SyntaxError".to_string()),
+        }
+    );
+}
+
+#[test]
+fn byte_order_mark() {
+    let dir = TestDir::new();
+    let source = with_meta("description: bom");
+    dir.write("bom.js", format!("\u{feff}{}", source));
+    let test = only_test(&dir);
+    assert_eq!(test.source, source);
+    assert_eq!(test.license(), Some(LICENSE));
+    assert_eq!(test.desc.description.as_deref(), Some("bom"));
+}
+
+#[test]
+fn body() {
+    let dir = TestDir::new();
+    dir.write(
+        "body.js",
+        format!("{}var x = 1;\n", with_meta("description: body")),
+    );
+    let test = only_test(&dir);
+    assert_eq!(test.body(), "\nvar x = 1;\n");
+}
+
+#[test]
+fn has_leading_directive() {
+    let cases = [
+        ("'use strict';\nvar x;", true),
+        (
+            "// leading comment\n/* another */\n\"use strict\"\nvar x;",
+            true,
+        ),
+        ("\"a\";\n'use strict';", true),
+        ("\"use strict\"", true),
+        ("\"use strict\" + x;", false),
+        ("\"use strict\"\n.length;", false),
+        ("var s = \"use strict\";", false),
+        ("// \"use strict\";\nvar x;", false),
+        ("`use strict`;", false),
+        ("", false),
+    ];
+    for (body, expected) in cases {
+        let dir = TestDir::new();
+        dir.write(
+            "directive.js",
+            format!("{}\n{}", with_meta("description: directive"), body),
+        );
+        assert_eq!(
+            only_test(&dir).has_leading_directive(),
+            expected,
+            "{:?}",
+            body
+        );
+    }
+}
+
+#[test]
+fn category() {
+    let dir = TestDir::new();
+    dir.write("built-ins/Array/a.js", with_meta("description: a"));
+    dir.write("top.js", with_meta("description: top"));
+    let mut harness = test262_harness::Harness::builder(dir.path())
+        .sorted(true)
+        .build()
+        .unwrap();
+    assert_eq!(
+        harness.next().unwrap().unwrap().category(),
+        Some("built-ins")
+    );
+    assert_eq!(harness.next().unwrap().unwrap().category(), None);
+}
+
+#[test]
+fn is_intl() {
+    let cases = [
+        ("intl402/a.js", "description: a", true),
+        ("built-ins/a.js", "locale: [en-US]", true),
+        ("built-ins/a.js", "features: [Intl.Locale]", true),
+        ("built-ins/a.js", "features: [BigInt]", false),
+    ];
+    for (path, meta, expected) in cases {
+        let dir = TestDir::new();
+        dir.write(path, with_meta(meta));
+        assert_eq!(only_test(&dir).is_intl(), expected, "{} {}", path, meta);
+    }
+}
+
+#[test]
+fn validate_async() {
+    let cases = [
+        (
+            "flags: [async]",
+            "Promise.resolve().then($DONE, $DONE);",
+            Ok(()),
+        ),
+        (
+            "flags: [async]\nincludes: [asyncHelpers.js]",
+            "asyncTest(async function() {});",
+            Ok(()),
+        ),
+        (
+            "flags: [async]",
+            "// $DONE is never called\nvar s = '$DONE';",
+            Err(ValidationError::AsyncWithoutDone),
+        ),
+        (
+            "description: sync",
+            "$DONE();",
+            Err(ValidationError::DoneWithoutAsync),
+        ),
+        ("description: sync", "assert(true);", Ok(())),
+    ];
+    for (meta, body, expected) in cases {
+        let dir = TestDir::new();
+        dir.write("a.js", format!("{}{}", with_meta(meta), body));
+        assert_eq!(only_test(&dir).validate_async(), expected, "{}", body);
+    }
+}
+
+#[test]
+fn to_original_position() {
+    let dir = TestDir::new();
+    let source = format!("{}var é = 1;\r\nvar x\r2;\n", with_meta("description: pos"));
+    dir.write("a.js", &source);
+    let test = only_test(&dir);
+    let body = source.find("var é").unwrap();
+    assert_eq!(test.to_original_position(0), Some((1, 1)));
+    assert_eq!(test.to_original_position(body), Some((6, 1)));
+    assert_eq!(
+        test.to_original_position(body + "var é".len()),
+        Some((6, 6))
+    );
+    let x = source.find("var x").unwrap();
+    assert_eq!(test.to_original_position(x), Some((7, 1)));
+    assert_eq!(test.to_original_position(x + "var x\r".len()), Some((8, 1)));
+    assert_eq!(test.to_original_position(source.len()), Some((9, 1)));
+    assert_eq!(test.to_original_position(body + "var ".len() + 1), None);
+    assert_eq!(test.to_original_position(source.len() + 1), None);
+}
+
+#[test]
+fn test_is_send_and_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<test262_harness::Test>();
+}
+
+#[test]
+fn needs_agent() {
+    let cases = [
+        (
+            "flags: [CanBlockIsFalse]",
+            "$262.agent.start(`...`);",
+            true,
+            Some(false),
+        ),
+        (
+            "flags: [CanBlockIsTrue]",
+            "$262.agent.broadcast(sab);",
+            true,
+            Some(true),
+        ),
+        (
+            "flags: [CanBlockIsFalse]",
+            "Atomics.wait(view, 0, 0);",
+            false,
+            Some(false),
+        ),
+        (
+            "description: a",
+            "// $262.agent.start()\nvar a = '$262.agent.start';",
+            false,
+            None,
+        ),
+        ("description: a", "other.$262.agent.start();", false, None),
+        ("description: a", "$262.agent.sleep(10);", false, None),
+    ];
+    for (meta, body, agent, can_block) in cases {
+        let dir = TestDir::new();
+        dir.write("a.js", format!("{}{}", with_meta(meta), body));
+        let test = only_test(&dir);
+        assert_eq!(test.needs_agent(), agent, "{}", body);
+        assert_eq!(test.can_block(), can_block, "{}", meta);
+    }
+}
+
+#[test]
+fn stable_id() {
+    let cases = [
+        ("id: my-id\nesid: sec-a", "my-id"),
+        ("es6id: 1.2.3\nesid: sec-a", "sec-a"),
+        ("es5id: 15.4.4\nes6id: 1.2.3", "15.4.4"),
+        ("description: none", "built-ins/Array/a.js"),
+    ];
+    for (meta, expected) in cases {
+        let dir = TestDir::new();
+        dir.write("built-ins/Array/a.js", with_meta(meta));
+        assert_eq!(only_test(&dir).stable_id(), expected);
+    }
+}
+
+#[test]
+fn is_annex_b() {
+    let cases = [
+        ("annexB/built-ins/escape/a.js", true),
+        ("built-ins/annexB/a.js", false),
+        ("annexB.js", false),
+    ];
+    for (path, expected) in cases {
+        let dir = TestDir::new();
+        dir.write(path, with_meta("description: a"));
+        assert_eq!(only_test(&dir).is_annex_b(), expected, "{}", path);
+    }
+}
+
+#[test]
+fn validate() {
+    let cases = [
+        ("flags: [raw]", "", Ok(())),
+        (
+            "flags: [raw, onlyStrict]",
+            "",
+            Err(ValidationError::RawConflict(Flag::OnlyStrict)),
+        ),
+        (
+            "flags: [noStrict, raw]",
+            "",
+            Err(ValidationError::RawConflict(Flag::NoStrict)),
+        ),
+        ("flags: [onlyStrict]", "", Ok(())),
+        ("flags: [async]", "", Err(ValidationError::AsyncWithoutDone)),
+    ];
+    for (meta, body, expected) in cases {
+        let dir = TestDir::new();
+        dir.write("a.js", format!("{}{}", with_meta(meta), body));
+        assert_eq!(only_test(&dir).validate(), expected, "{}", meta);
+    }
+}
+
+#[test]
+fn raw_bytes() {
+    let dir = TestDir::new();
+    let mut bytes = with_meta("description: bytes").into_bytes();
+    bytes.extend_from_slice(b"var s = '\xed\xa0\x80';\n");
+    dir.write("a.js", &bytes);
+    let test = only_test(&dir);
+    assert_eq!(test.raw_bytes(), bytes.as_slice());
+    assert!(test.body().contains('\u{fffd}'));
+
+    let dir = TestDir::new();
+    let source = with_meta("description: bom");
+    dir.write("a.js", format!("\u{feff}{}", source));
+    let test = only_test(&dir);
+    assert_eq!(test.source, source);
+    assert!(test.raw_bytes().starts_with("\u{feff}".as_bytes()));
+
+    let dir = TestDir::new();
+    dir.write("a.js", &source);
+    assert_eq!(only_test(&dir).raw_bytes(), source.as_bytes());
+}
+
+#[test]
+fn imported_fixtures() {
+    let dir = TestDir::new();
+    let body = r#"
+import { x } from "./a_FIXTURE.js";
+import './b_FIXTURE.js';
+export * from "../shared/c_FIXTURE.js";
+import("./d_FIXTURE.js").then($DONE);
+import { y } from './a_FIXTURE.js';
+import json from "./data.json" with { type: "json" };
+// import "./commented_FIXTURE.js";
+var s = 'from "./string_FIXTURE.js"';
+"#;
+    dir.write(
+        "language/module-code/t.js",
+        format!("{}{}", with_meta("flags: [module, async]"), body),
+    );
+    let module_code = dir.path().join("language").join("module-code");
+    assert_eq!(
+        only_test(&dir).imported_fixtures(),
+        [
+            module_code.join("a_FIXTURE.js"),
+            module_code.join("b_FIXTURE.js"),
+            dir.path()
+                .join("language")
+                .join("shared")
+                .join("c_FIXTURE.js"),
+            module_code.join("d_FIXTURE.js"),
+        ]
+    );
+}
+
+#[test]
+fn estimated_cost() {
+    use test262_harness::Cost;
+    let long = format!("var s = '{}';", "x".repeat(4 * 1024));
+    let huge = format!("var s = '{}';", "x".repeat(20 * 1024));
+    let cases = [
+        ("assert.sameValue(1, 1);", Cost::Small),
+        ("var big = 0x10FFFF;", Cost::Small),
+        ("for (var i = 0; i < 10; i++) {}", Cost::Medium),
+        ("// for (;;) with 0x10FFFF\nvar s = 'while';", Cost::Small),
+        (long.as_str(), Cost::Medium),
+        ("for (var i = 0; i < 0x10FFFF; i++) {}", Cost::Large),
+        ("var i = 0; while (i < 65_536) i++;", Cost::Large),
+        (huge.as_str(), Cost::Large),
+    ];
+    for (body, expected) in cases {
+        let dir = TestDir::new();
+        dir.write(
+            "a.js",
+            format!("{}{}", with_meta("description: cost"), body),
+        );
+        assert_eq!(only_test(&dir).estimated_cost(), expected, "{:.40}", body);
+    }
+}
+
+#[test]
+fn section_path() {
+    let dir = TestDir::new();
+    dir.write(
+        "built-ins/Array/prototype/flat/a.js",
+        with_meta("description: a"),
+    );
+    assert_eq!(
+        only_test(&dir).section_path(),
+        std::path::Path::new("built-ins/Array/prototype/flat")
+    );
+    let dir = TestDir::new();
+    dir.write("top.js", with_meta("description: top"));
+    assert_eq!(only_test(&dir).section_path(), std::path::Path::new(""));
+}
+
+#[test]
+fn uses_top_level_await() {
+    let cases = [
+        ("await Promise.resolve();", true),
+        ("if (x) { await x; }", true),
+        ("for await (const x of xs) {}", true),
+        ("const { a } = await import('./a_FIXTURE.js');", true),
+        ("label: { var x = await y; }", true),
+        ("async function f() { await x; }", false),
+        ("const f = async () => { await x; };", false),
+        ("const f = async () => await x;", false),
+        ("class C { async m() { await x; } }", false),
+        ("var o = { async m() { if (a) { await b; } } };", false),
+        ("// await x\nvar s = 'await';", false),
+        ("var t = `${x}await`;", false),
+        ("o.await; var p = { await: 1 };", false),
+        ("const f = async () => await x;\nawait f();", true),
+    ];
+    for (body, expected) in cases {
+        let dir = TestDir::new();
+        dir.write("a.js", format!("{}{}", with_meta("flags: [module]"), body));
+        assert_eq!(only_test(&dir).uses_top_level_await(), expected, "{}", body);
+    }
+    let dir = TestDir::new();
+    dir.write(
+        "a.js",
+        format!("{}await;", with_meta("description: script")),
+    );
+    assert!(!only_test(&dir).uses_top_level_await());
+}
+
+#[test]
+fn run_as_module() {
+    use test262_harness::{Harness, HarnessConfig, ModuleReason, RunMode};
+    let dir = TestDir::new();
+    dir.write("a.js", with_meta("flags: [module]"));
+    dir.write("b.mjs", with_meta("description: extension only"));
+    dir.write("c.mjs", with_meta("flags: [module]"));
+    dir.write("d.js", with_meta("description: script"));
+    let config = HarnessConfig {
+        extensions: vec!["js".to_string(), "mjs".to_string()],
+        ..Default::default()
+    };
+    let tests: Vec<_> = Harness::builder(dir.path())
+        .sorted(true)
+        .config(config)
+        .build()
+        .unwrap()
+        .map(Result::unwrap)
+        .collect();
+    let reasons: Vec<_> = tests.iter().map(|test| test.module_reason()).collect();
+    assert_eq!(
+        reasons,
+        [
+            Some(ModuleReason::Flag),
+            Some(ModuleReason::Extension),
+            Some(ModuleReason::Flag),
+            None,
+        ]
+    );
+    assert!(tests[1].run_as_module());
+    assert!(!tests[3].run_as_module());
+    assert_eq!(tests[1].execution_plan(), [RunMode::Module]);
+    assert_eq!(
+        tests[3].execution_plan(),
+        [RunMode::NonStrict, RunMode::Strict]
+    );
+}
+
+#[test]
+fn body_range() {
+    let dir = TestDir::new();
+    let source = format!("{}x();\n", with_meta("description: range"));
+    dir.write("a.js", &source);
+    let test = only_test(&dir);
+    assert_eq!(
+        test.body_range(),
+        source.find("---*/").unwrap() + 5..source.len()
+    );
+    assert_eq!(test.body(), "\nx();\n");
+
+    let dir = TestDir::new();
+    let source = format!("{}x();\n", with_meta("flags: [raw]"));
+    dir.write("a.js", &source);
+    let test = only_test(&dir);
+    assert_eq!(test.body_range(), 0..source.len());
+    assert_eq!(test.body(), source);
+}
+
+#[test]
+fn nested_metadata_markers() {
+    let dir = TestDir::new();
+    dir.write(
+        "a.js",
+        format!(
+            "// the metadata opens with /*--- and closes with ---*/\n{}\n/*---\ndescription: markers\ninfo: |\n  /*--- quoted spec text ---*/\n  ---*/\nflags: [onlyStrict]\n---*/\nbody();\n",
+            LICENSE
+        ),
+    );
+    let test = only_test(&dir);
+    assert_eq!(test.desc.description.as_deref(), Some("markers"));
+    assert_eq!(
+        test.desc.info.as_deref(),
+        Some("/*--- quoted spec text ---*/\n---*/\n")
+    );
+    assert_eq!(test.desc.flags, [Flag::OnlyStrict]);
+    assert_eq!(test.body(), "\nbody();\n");
+    assert_eq!(test.license(), Some(LICENSE));
+}
+
+#[test]
+fn referenced_harness_apis() {
+    let cases: &[(&str, &[&str])] = &[
+        ("var a = 1;", &[]),
+        (
+            "verifyProperty(o, 'a', {});\nassert.compareArray([], []);",
+            &["assert.js", "compareArray.js", "propertyHelper.js"],
+        ),
+        (
+            "testWithTypedArrayConstructors(function(TA) { throw new Test262Error(); });",
+            &["sta.js", "testTypedArray.js"],
+        ),
+        ("// verifyProperty(o)\nvar s = 'assert.deepEqual';", &[]),
+        (
+            "asyncTest(async () => {});\n$DONE();",
+            &["doneprintHandle.js", "asyncHelpers.js"],
+        ),
+    ];
+    for (body, files) in cases {
+        let dir = TestDir::new();
+        dir.write("a.js", format!("{}{}", with_meta("description: a"), body));
+        let test = only_test(&dir);
+        assert_eq!(test.referenced_harness_apis(), *files, "{}", body);
+    }
+}
+
+#[test]
+fn reparse_metadata() {
+    let dir = TestDir::new();
+    dir.write(
+        "a.js",
+        format!("{}body();\n", with_meta("description: short")),
+    );
+    let mut test = only_test(&dir);
+    let range = test.metadata_range();
+    assert_eq!(&test.source[range.clone()], "\ndescription: short\n");
+    test.reparse_metadata("\ndescription: a much longer one\nflags: [onlyStrict]\n")
+        .unwrap();
+    assert_eq!(test.desc.description.as_deref(), Some("a much longer one"));
+    assert_eq!(test.desc.flags, [Flag::OnlyStrict]);
+    assert_eq!(test.body(), "\nbody();\n");
+    assert_eq!(test.license(), Some(LICENSE));
+    assert_eq!(
+        &test.source[test.metadata_range()],
+        "\ndescription: a much longer one\nflags: [onlyStrict]\n"
+    );
+    let before = test.source.clone();
+    assert!(test.reparse_metadata("\nflags: [notAFlag]\n").is_err());
+    assert!(test.reparse_metadata("\n---*/\n").is_err());
+    assert_eq!(test.source, before);
+    assert_eq!(test.desc.flags, [Flag::OnlyStrict]);
+}
+
+#[test]
+fn reparse_metadata_harness_settings() {
+    let dir = TestDir::new();
+    dir.write("harness/assert.js", "");
+    dir.write("harness/sta.js", "");
+    dir.write("test/a.js", with_meta("description: a"));
+    let mut test = test262_harness::Harness::builder(dir.path().join("test"))
+        .harness_dir(dir.path().join("harness"))
+        .validate_includes(true)
+        .deny_duplicate_keys(true)
+        .build()
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert!(matches!(
+        test.reparse_metadata("\ndescription: a\ndescription: b\n"),
+        Err(test262_harness::Error::DuplicateMetadataKey { key, .. }) if key == "description"
+    ));
+    assert!(matches!(
+        test.reparse_metadata("\ndescription: a\nincludes: [missing.js]\n"),
+        Err(test262_harness::Error::MissingInclude { include, .. }) if include == "missing.js"
+    ));
+    assert!(test.desc.includes.is_empty());
+    test.reparse_metadata("\ndescription: b\n").unwrap();
+    assert_eq!(test.desc.description.as_deref(), Some("b"));
+}
+
+#[test]
+fn runs_both_modes() {
+    let modal = [Flag::Raw, Flag::Module, Flag::OnlyStrict, Flag::NoStrict];
+    // every subset of the flags that pick a mode,
+    // alongside one that doesn't
+    for mask in 0..1 << modal.len() {
+        let mut flags: Vec<_> = (0..modal.len())
+            .filter(|bit| mask & (1 << bit) != 0)
+            .map(|bit| modal[bit].to_string())
+            .collect();
+        flags.push(Flag::Generated.to_string());
+        let dir = TestDir::new();
+        dir.write("a.js", with_meta(&format!("flags: [{}]", flags.join(", "))));
+        let test = only_test(&dir);
+        assert_eq!(test.runs_both_modes(), mask == 0, "{:?}", flags);
+        assert_eq!(
+            test.runs_both_modes(),
+            test.execution_plan().len() == 2,
+            "{:?}",
+            flags
+        );
+    }
+}
+
+#[test]
+fn required_agent_apis() {
+    let cases: &[(&str, &[&str])] = &[
+        ("var a = 1;", &[]),
+        (
+            "$262.agent.start(`\n  $262.agent.receiveBroadcast(function(sab) {\n    $262.agent.report(1);\n    $262.agent.leaving();\n  });\n`);\n$262.agent.broadcast(sab);\n$262.agent.sleep(10);\nwhile ($262.agent.getReport() == null) {}\n$262.agent.sleep(10);",
+            &[
+                "start",
+                "broadcast",
+                "sleep",
+                "getReport",
+                "receiveBroadcast",
+                "report",
+                "leaving",
+            ],
+        ),
+        (
+            "$262.agent.start('$262.agent.report($262.agent.monotonicNow());');",
+            &["start", "report", "monotonicNow"],
+        ),
+        (
+            "// $262.agent.sleep(1)\nvar s = '$262.agent.sleep';\nother.$262.agent.sleep();",
+            &[],
+        ),
+    ];
+    for (body, apis) in cases {
+        let dir = TestDir::new();
+        dir.write("a.js", format!("{}{}", with_meta("description: a"), body));
+        let test = only_test(&dir);
+        assert_eq!(test.required_agent_apis(), *apis, "{}", body);
+    }
+}
+
+#[test]
+fn inline_harness() {
+    let dir = TestDir::new();
+    dir.write("harness/assert.js", "var assert = {};");
+    dir.write("harness/sta.js", "function Test262Error() {}\n");
+    dir.write("harness/compareArray.js", "function compareArray() {}\n");
+    dir.write("harness/temporalHelpers.js", "var TemporalHelpers = {};\n");
+    let harness_dir = dir.path().join("harness");
+    dir.write(
+        "test/a.js",
+        format!(
+            "{}body();\n",
+            with_meta("flags: [onlyStrict]\nincludes: [temporalHelpers.js]")
+        ),
+    );
+    dir.write(
+        "test/b.js",
+        format!("{}raw();\n", with_meta("flags: [raw]")),
+    );
+    dir.write(
+        "test/c.js",
+        format!("{}export {{}};\n", with_meta("flags: [module]")),
+    );
+    dir.write("test/d.js", with_meta("includes: [missing.js]"));
+    let tests: Vec<_> = test262_harness::Harness::builder(dir.path().join("test"))
+        .sorted(true)
+        .build()
+        .unwrap()
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(
+        tests[0].inline_harness(&harness_dir).unwrap(),
+        "\"use strict\";\n// assert.js\nvar assert = {};\n// sta.js\nfunction Test262Error() {}\n// compareArray.js\nfunction compareArray() {}\n// temporalHelpers.js\nvar TemporalHelpers = {};\n// a.js\n\nbody();\n"
+    );
+    assert_eq!(
+        tests[1].inline_harness(&harness_dir).unwrap(),
+        tests[1].source
+    );
+    assert!(matches!(
+        tests[2].inline_harness(&harness_dir),
+        Err(test262_harness::Error::InlineModule(_))
+    ));
+    assert!(matches!(
+        tests[3].inline_harness(&harness_dir),
+        Err(test262_harness::Error::MissingInclude { include, .. }) if include == "missing.js"
+    ));
+}
+
+#[test]
+fn inline_harness_override() {
+    let dir = TestDir::new();
+    dir.write("harness/assert.js", "var assert = {};\n");
+    dir.write("harness/sta.js", "function Test262Error() {}\n");
+    let patched = dir.write("engine/sta.js", "function Test262Error(message) {}\n");
+    dir.write(
+        "test/a.js",
+        format!("{}body();\n", with_meta("description: a")),
+    );
+    let test = test262_harness::Harness::builder(dir.path().join("test"))
+        .override_include("sta.js", &patched)
+        .build()
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        test.inline_harness(&dir.path().join("harness")).unwrap(),
+        "// assert.js\nvar assert = {};\n// sta.js\nfunction Test262Error(message) {}\n// a.js\n\nbody();\n"
+    );
+}
+
+#[test]
+fn invocation() {
+    let invocation = |module, strict, includes: &[&str], is_async| Invocation {
+        module,
+        strict,
+        includes: includes.iter().map(|i| i.to_string()).collect(),
+        is_async,
+    };
+    let standard = ["assert.js", "sta.js"];
+    let cases = [
+        (
+            "description: both",
+            vec![
+                invocation(false, false, &standard, false),
+                invocation(false, true, &standard, false),
+            ],
+        ),
+        (
+            "flags: [onlyStrict, async]",
+            vec![invocation(
+                false,
+                true,
+                &["assert.js", "sta.js", "doneprintHandle.js"],
+                true,
+            )],
+        ),
+        (
+            "flags: [module]\nincludes: [compareArray.js]",
+            vec![invocation(
+                true,
+                false,
+                &["assert.js", "sta.js", "compareArray.js"],
+                false,
+            )],
+        ),
+        ("flags: [raw]", vec![invocation(false, false, &[], false)]),
+    ];
+    for (meta, expected) in cases {
+        let dir = TestDir::new();
+        dir.write("a.js", format!("{}$DONE();", with_meta(meta)));
+        let test = only_test(&dir);
+        assert_eq!(test.invocation(), expected, "{}", meta);
+    }
+}